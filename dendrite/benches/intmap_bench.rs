@@ -0,0 +1,57 @@
+//! Compares [`IntMap`] against a general-purpose `HashMap<usize, _>` for
+//! the access pattern `TraceStats::get_mut` puts it through: repeated
+//! `entry`-or-insert lookups keyed on dense-ish program counter values.
+
+use std::collections::HashMap;
+
+use criterion::{
+    criterion_group, criterion_main, BenchmarkId, Criterion, Throughput,
+};
+
+use dendrite::analysis::IntMap;
+
+/// A trace's worth of synthetic PCs: a handful of hot loops (narrow,
+/// densely-revisited ranges) plus some cold one-off branches scattered
+/// across a wider range, which is roughly how real traces distribute.
+fn synthetic_pcs(n: usize) -> Vec<usize> {
+    (0..n)
+        .map(|i| {
+            if i % 4 != 0 {
+                0x1000 + (i % 64) * 4
+            } else {
+                0x10000 + (i.wrapping_mul(2654435761) % 0x100000)
+            }
+        })
+        .collect()
+}
+
+fn bench_get_or_insert(c: &mut Criterion) {
+    let pcs = synthetic_pcs(100_000);
+    let mut group = c.benchmark_group("get_or_insert");
+    group.throughput(Throughput::Elements(pcs.len() as u64));
+
+    group.bench_with_input(BenchmarkId::new("IntMap", pcs.len()), &pcs, |b, pcs| {
+        b.iter(|| {
+            let mut map: IntMap<usize> = IntMap::new();
+            for &pc in pcs {
+                *map.entry_or_insert_with(pc, || 0) += 1;
+            }
+            map.len()
+        });
+    });
+
+    group.bench_with_input(BenchmarkId::new("HashMap", pcs.len()), &pcs, |b, pcs| {
+        b.iter(|| {
+            let mut map: HashMap<usize, usize> = HashMap::new();
+            for &pc in pcs {
+                *map.entry(pc).or_insert(0) += 1;
+            }
+            map.len()
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_or_insert);
+criterion_main!(benches);
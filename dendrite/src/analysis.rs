@@ -3,10 +3,16 @@
 pub mod stats;
 pub mod runlength;
 pub mod classify;
+pub mod wavelet;
+pub mod segtree;
+pub mod intmap;
 
 pub use stats::*;
 pub use runlength::*;
 pub use classify::*;
+pub use wavelet::*;
+pub use segtree::*;
+pub use intmap::*;
 
 use bitvec::prelude::*;
 use crate::Outcome;
@@ -87,6 +93,24 @@ impl BranchOutcomes {
         self.num_not_taken() as f64 / self.data.len() as f64
     }
 
+    /// Fold this outcome history into a 64-bit fingerprint, so that two
+    /// [BranchOutcomes] with identical length and content always collide
+    /// and (almost) any other pair won't.
+    ///
+    /// This is a fixed-seed multiply-mix over each 64-bit word of the
+    /// underlying [BitSlice] plus the bit length, not a cryptographic
+    /// hash - it's meant for cheap clustering, not adversarial inputs.
+    pub fn fingerprint(&self) -> u64 {
+        const SEED: u64 = 0x9e3779b97f4a7c15;
+        let mut h: u64 = SEED ^ (self.data.len() as u64);
+        for word in self.data.as_raw_slice() {
+            h ^= *word as u64;
+            h = h.wrapping_mul(SEED);
+            h ^= h >> 33;
+        }
+        h
+    }
+
     pub fn into_outcomes(&self) -> Vec<Outcome> {
         Outcome::vec_from_bitvec(&self.data)
     }
@@ -166,7 +190,7 @@ impl BranchData {
         self.hits as f64 / self.occ as f64
     }
 
-    fn has_uniform_runs(pairs: &[RunPair<Outcome>]) 
+    fn has_uniform_runs(pairs: &[RunPair<Outcome>])
         -> Option<Vec<RunPair<Outcome>>>
     {
         let window = &pairs;
@@ -184,29 +208,77 @@ impl BranchData {
         None
     }
 
-    /// Classify this branch using the observed outcomes. 
-    pub fn classify(&self) -> BranchClass {
-        // The outcome is always the same
-        if let Some(outcome) = self.outcomes.is_static() {
-            return BranchClass::Static(outcome);
+    /// Like [BranchData::has_uniform_runs], but allows some fixed number
+    /// of leading pairs to be skipped first, for branches that settle
+    /// into a repeating pattern only after an initial prefix. Returns the
+    /// prefix length (in outcomes, not pairs) and the flattened repeating
+    /// pattern.
+    fn has_uniform_runs_prefixed(pairs: &[RunPair<Outcome>])
+        -> Option<(usize, Vec<Outcome>)>
+    {
+        for skip in 1..pairs.len().saturating_sub(1) {
+            let rest = &pairs[skip..];
+            if let Some(pattern) = Self::has_uniform_runs(rest) {
+                let prefix_len: usize = pairs[0..skip].iter()
+                    .map(|p| p.head().count() + p.tail().count())
+                    .sum();
+                let flat: Vec<Outcome> = pattern.iter()
+                    .flat_map(|p| {
+                        std::iter::repeat(*p.head().value()).take(p.head().count())
+                            .chain(std::iter::repeat(*p.tail().value()).take(p.tail().count()))
+                    })
+                    .collect();
+                return Some((prefix_len, flat));
+            }
         }
+        None
+    }
 
+    /// Classify this branch using the observed outcomes, returning the
+    /// class together with the run-length encoding (pairs of taken/
+    /// not-taken runs) it was derived from, so callers can inspect the
+    /// periodicity a classification like [BranchClass::UniformPattern]
+    /// is exploiting.
+    pub fn classify(&self) -> (BranchClass, Vec<RunPair<Outcome>>) {
         let pairs = self.outcomes.into_pairs();
-        if let Some(pattern) = Self::has_uniform_runs(&pairs) {
-            return BranchClass::UniformPattern(pattern);
+
+        // The outcome is always the same (a single run).
+        if let Some(outcome) = self.outcomes.is_static() {
+            return (BranchClass::Static(outcome), pairs);
         }
 
+        // Exactly one transition: a single head/tail pair covers the
+        // entire history. (`into_pairs` drops a trailing unpaired run, so
+        // a single pair alone doesn't rule out a second transition.)
+        if pairs.len() == 1 && self.outcomes.into_runs().len() == 2 {
+            return (BranchClass::SinglePair(pairs[0].clone()), pairs);
+        }
+
+        let head_const = pairs.iter().all(|p| p.head().count() == pairs[0].head().count());
+        let tail_const = pairs.iter().all(|p| p.tail().count() == pairs[0].tail().count());
+
+        // The head of every pair is a fixed length, but the tail varies.
+        if head_const && !tail_const {
+            return (BranchClass::StaticHead(pairs.clone()), pairs);
+        }
 
-        //if let Some(pattern) = self.outcomes.has_uniform_pattern() {
-        //    return BranchClass::UniformPattern(pattern);
-        //}
+        // The tail of every pair is a fixed length, but the head varies.
+        if tail_const && !head_const {
+            return (BranchClass::StaticTail(pairs.clone()), pairs);
+        }
 
-        //if let Some(pattern) = self.outcomes.has_uniform_pattern_prefixed() {
-        //    return BranchClass::UniformPatternPrefixed(1, pattern);
-        //}
+        // One RunPair (or a short sequence of them) repeats to cover the
+        // entire history.
+        if let Some(pattern) = Self::has_uniform_runs(&pairs) {
+            return (BranchClass::UniformPattern(pattern), pairs);
+        }
 
+        // A fixed prefix is followed by a uniform repeat covering the rest.
+        if let Some((prefix_len, pattern)) = Self::has_uniform_runs_prefixed(&pairs) {
+            return (BranchClass::UniformPatternPrefixed(prefix_len, pattern), pairs);
+        }
 
-        BranchClass::Unknown
+        (BranchClass::Unknown, pairs)
     }
 
 }
@@ -0,0 +1,81 @@
+//! A purpose-built map over `usize` keys, used in place of a general
+//! hash map wherever keys are dense-ish integers (program counter values
+//! are the motivating case: traces over a single binary cluster into a
+//! handful of text-segment-sized ranges).
+//!
+//! Keys are split into a high part (selecting a page) and a low part
+//! (indexing within it), so a lookup or insert is an array index rather
+//! than a hash plus probe sequence - at the cost of allocating a full
+//! page the first time any key in its range is touched.
+
+/// Number of low bits of a key used to index within a page.
+const PAGE_BITS: u32 = 12;
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+const PAGE_MASK: usize = PAGE_SIZE - 1;
+
+/// A two-level page table keyed on `usize`, used in place of a
+/// general-purpose hash map for dense-ish integer keys (e.g. program
+/// counter values).
+pub struct IntMap<V> {
+    pages: Vec<Option<Box<[Option<V>]>>>,
+    len: usize,
+}
+impl<V> IntMap<V> {
+    pub fn new() -> Self {
+        Self { pages: Vec::new(), len: 0 }
+    }
+
+    fn split(key: usize) -> (usize, usize) {
+        (key >> PAGE_BITS, key & PAGE_MASK)
+    }
+
+    /// Number of entries currently stored.
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Returns a reference to the value at `key`, if present.
+    pub fn get(&self, key: usize) -> Option<&V> {
+        let (page_idx, offset) = Self::split(key);
+        self.pages.get(page_idx)?.as_ref()?[offset].as_ref()
+    }
+
+    /// Returns a mutable reference to the value at `key`, if present.
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut V> {
+        let (page_idx, offset) = Self::split(key);
+        self.pages.get_mut(page_idx)?.as_mut()?[offset].as_mut()
+    }
+
+    /// Returns a mutable reference to the value at `key`, inserting
+    /// `default()` first if one isn't already present.
+    pub fn entry_or_insert_with(&mut self, key: usize, default: impl FnOnce() -> V)
+        -> &mut V
+    {
+        let (page_idx, offset) = Self::split(key);
+        if page_idx >= self.pages.len() {
+            self.pages.resize_with(page_idx + 1, || None);
+        }
+        let page = self.pages[page_idx].get_or_insert_with(|| {
+            (0..PAGE_SIZE).map(|_| None).collect::<Vec<_>>().into_boxed_slice()
+        });
+        if page[offset].is_none() {
+            page[offset] = Some(default());
+            self.len += 1;
+        }
+        page[offset].as_mut().unwrap()
+    }
+
+    /// Iterate over `(key, value)` pairs in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &V)> {
+        self.pages.iter().enumerate().filter_map(|(page_idx, page)| {
+            page.as_ref().map(|p| (page_idx, p))
+        }).flat_map(|(page_idx, page)| {
+            page.iter().enumerate().filter_map(move |(offset, v)| {
+                v.as_ref().map(|v| ((page_idx << PAGE_BITS) | offset, v))
+            })
+        })
+    }
+}
+impl<V> Default for IntMap<V> {
+    fn default() -> Self { Self::new() }
+}
@@ -0,0 +1,423 @@
+//! A segment tree over a hit/miss stream, with lazy range-assignment for
+//! "what-if" masking and support for windowed accuracy queries, plus a
+//! generic lazy-propagation segment tree for range ops over predictor
+//! tables (periodic decay, hotspot queries).
+
+/// A node summarizing some contiguous range of the hit/miss stream.
+#[derive(Clone, Copy, Debug, Default)]
+struct Node {
+    /// Number of hits observed in this range.
+    hits: usize,
+    /// Number of branches (leaves) covered by this range.
+    count: usize,
+    /// Pending range-assignment: `Some(true)` means "treat every leaf in
+    /// this range as a hit", `Some(false)` means "treat every leaf as a
+    /// miss". Must be pushed to children before any descent that splits
+    /// the range.
+    lazy: Option<bool>,
+}
+
+/// A segment tree over a per-record hit/miss stream (one bit per
+/// conditional branch, in trace order), answering accuracy questions over
+/// any interval `[l, r)` in O(log n).
+///
+/// Non-power-of-two lengths are padded with `(0, 0)` identity leaves, so
+/// they don't skew `count`/`hits` sums.
+pub struct HitMissTree {
+    /// Number of real leaves (before padding to a power of two).
+    len: usize,
+    /// `nodes[1]` is the root; `nodes[2*i]`/`nodes[2*i+1]` are the
+    /// children of `nodes[i]`.
+    nodes: Vec<Node>,
+    /// Number of leaves after padding to a power of two.
+    size: usize,
+}
+impl HitMissTree {
+    /// Build a tree from a stream of hit/miss bits (`true` = hit).
+    pub fn new(hits: &[bool]) -> Self {
+        let len = hits.len();
+        let size = len.next_power_of_two().max(1);
+        let mut nodes = vec![Node::default(); 2 * size];
+
+        for (i, &hit) in hits.iter().enumerate() {
+            nodes[size + i] = Node { hits: hit as usize, count: 1, lazy: None };
+        }
+        for i in (1..size).rev() {
+            nodes[i] = Self::combine(nodes[2 * i], nodes[2 * i + 1]);
+        }
+
+        Self { len, nodes, size }
+    }
+
+    fn combine(lhs: Node, rhs: Node) -> Node {
+        Node {
+            hits: lhs.hits + rhs.hits,
+            count: lhs.count + rhs.count,
+            lazy: None,
+        }
+    }
+
+    /// Stamp a pending assignment onto a node's own (hits, count), without
+    /// touching its children.
+    fn apply(node: &mut Node, assign: bool) {
+        node.hits = if assign { node.count } else { 0 };
+        node.lazy = Some(assign);
+    }
+
+    /// Push this node's pending tag down to its two children.
+    fn push_down(nodes: &mut [Node], idx: usize) {
+        if let Some(assign) = nodes[idx].lazy.take() {
+            Self::apply(&mut nodes[2 * idx], assign);
+            Self::apply(&mut nodes[2 * idx + 1], assign);
+        }
+    }
+
+    /// Number of branches recorded (before padding).
+    pub fn len(&self) -> usize { self.len }
+
+    /// Assign every leaf in `[l, r)` to `hit` (e.g. to mask out a branch
+    /// known to be static for "what-if" analysis).
+    pub fn assign_range(&mut self, l: usize, r: usize, hit: bool) {
+        self.assign_range_rec(1, 0, self.size, l, r, hit);
+    }
+
+    fn assign_range_rec(&mut self, idx: usize, lo: usize, hi: usize, l: usize, r: usize, hit: bool) {
+        if r <= lo || hi <= l {
+            return;
+        }
+        if l <= lo && hi <= r {
+            Self::apply(&mut self.nodes[idx], hit);
+            return;
+        }
+        Self::push_down(&mut self.nodes, idx);
+        let mid = (lo + hi) / 2;
+        self.assign_range_rec(2 * idx, lo, mid, l, r, hit);
+        self.assign_range_rec(2 * idx + 1, mid, hi, l, r, hit);
+        self.nodes[idx] = Self::combine(self.nodes[2 * idx], self.nodes[2 * idx + 1]);
+    }
+
+    /// Return `(hits, count)` for the range `[l, r)`.
+    pub fn query(&mut self, l: usize, r: usize) -> (usize, usize) {
+        let node = self.query_rec(1, 0, self.size, l, r);
+        (node.hits, node.count)
+    }
+
+    fn query_rec(&mut self, idx: usize, lo: usize, hi: usize, l: usize, r: usize) -> Node {
+        if r <= lo || hi <= l || l >= r {
+            return Node::default();
+        }
+        if l <= lo && hi <= r {
+            return self.nodes[idx];
+        }
+        Self::push_down(&mut self.nodes, idx);
+        let mid = (lo + hi) / 2;
+        let left = self.query_rec(2 * idx, lo, mid, l, r);
+        let right = self.query_rec(2 * idx + 1, mid, hi, l, r);
+        Self::combine(left, right)
+    }
+
+    /// Return the accuracy (hits / count) over `[l, r)`, or `None` if the
+    /// range is empty.
+    pub fn accuracy(&mut self, l: usize, r: usize) -> Option<f64> {
+        let (hits, count) = self.query(l, r);
+        if count == 0 {
+            None
+        } else {
+            Some(hits as f64 / count as f64)
+        }
+    }
+
+    /// Return hits over `[0, i)`.
+    pub fn prefix_hits(&mut self, i: usize) -> usize {
+        self.query(0, i).0
+    }
+
+    /// Scan all windows of length `w` and return `(start, accuracy)` for
+    /// the window with the lowest accuracy, or `None` if `w` is zero or
+    /// longer than the recorded stream.
+    ///
+    /// Each window's hit count is derived from two prefix-sum lookups, so
+    /// this runs in O(n log n) overall.
+    pub fn worst_window(&mut self, w: usize) -> Option<(usize, f64)> {
+        if w == 0 || w > self.len {
+            return None;
+        }
+        let mut worst: Option<(usize, f64)> = None;
+        for start in 0..=(self.len - w) {
+            let hits = self.prefix_hits(start + w) - self.prefix_hits(start);
+            let acc = hits as f64 / w as f64;
+            if worst.map_or(true, |(_, best)| acc < best) {
+                worst = Some((start, acc));
+            }
+        }
+        worst
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn query_and_accuracy_match_a_linear_scan() {
+        let hits = [true, true, false, true, false, false, true, true, true];
+        let mut tree = HitMissTree::new(&hits);
+
+        for l in 0..hits.len() {
+            for r in l..=hits.len() {
+                let expect_hits = hits[l..r].iter().filter(|&&h| h).count();
+                assert_eq!(tree.query(l, r), (expect_hits, r - l), "query({l}, {r})");
+            }
+        }
+        assert_eq!(tree.accuracy(0, hits.len()), Some(6.0 / 9.0));
+        assert_eq!(tree.accuracy(3, 3), None);
+    }
+
+    #[test]
+    fn assign_range_overrides_underlying_hits() {
+        let hits = [true, false, false, false, true, true];
+        let mut tree = HitMissTree::new(&hits);
+
+        tree.assign_range(1, 4, true);
+        assert_eq!(tree.query(1, 4), (3, 3));
+        assert_eq!(tree.query(0, 6), (6, 6));
+
+        tree.assign_range(0, 6, false);
+        assert_eq!(tree.query(0, 6), (0, 6));
+    }
+
+    #[test]
+    fn worst_window_finds_the_lowest_accuracy_span() {
+        let hits = [true, true, true, false, false, true, true, true];
+        let mut tree = HitMissTree::new(&hits);
+        assert_eq!(tree.worst_window(2), Some((3, 0.0)));
+        assert_eq!(tree.worst_window(0), None);
+        assert_eq!(tree.worst_window(hits.len() + 1), None);
+    }
+}
+
+/// A monoid aggregate summarizing a contiguous range of leaves in a
+/// [`LazySegTree`].
+pub trait Aggregate: Copy {
+    /// The aggregate of an empty range.
+    fn identity() -> Self;
+
+    /// Combine the aggregates of two adjacent ranges, left before right.
+    fn combine(lhs: Self, rhs: Self) -> Self;
+}
+
+/// A lazy action queued on a [`LazySegTree`] node, applied to its
+/// aggregate and composed with any pending action before being pushed
+/// down to its children.
+pub trait LazyAction<A: Aggregate>: Copy {
+    /// The action that leaves a range unchanged.
+    fn identity() -> Self;
+
+    /// Apply this action to the aggregate of a range covering `count`
+    /// leaves.
+    fn apply(&self, agg: A, count: usize) -> A;
+
+    /// Compose `self`, applied after `prev`, into a single action
+    /// equivalent to applying `prev` then `self`.
+    fn compose(&self, prev: Self) -> Self;
+}
+
+/// A range action over saturating-counter-style entries: `Assign`
+/// overwrites every leaf in the range (e.g. flushing a region on a
+/// simulated context switch), `Add` biases every leaf by a delta (e.g. a
+/// global decay or boost). Composing two actions keeps the later
+/// `Assign` outright; composing an `Add` after an `Assign` folds the
+/// delta into the assigned value; two `Add`s sum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeAction<T> {
+    Assign(T),
+    Add(T),
+}
+impl LazyAction<i64> for RangeAction<i64> {
+    fn identity() -> Self { Self::Add(0) }
+
+    fn apply(&self, agg: i64, count: usize) -> i64 {
+        match self {
+            Self::Assign(v) => v * count as i64,
+            Self::Add(v) => agg + v * count as i64,
+        }
+    }
+
+    fn compose(&self, prev: Self) -> Self {
+        match (self, prev) {
+            (Self::Assign(v), _) => Self::Assign(*v),
+            (Self::Add(d), Self::Assign(v)) => Self::Assign(v + d),
+            (Self::Add(d), Self::Add(p)) => Self::Add(p + d),
+        }
+    }
+}
+
+impl Aggregate for i64 {
+    fn identity() -> Self { 0 }
+    fn combine(lhs: Self, rhs: Self) -> Self { lhs + rhs }
+}
+
+/// The maximum of a range, as an [`Aggregate`] for hotspot queries (e.g.
+/// "which region of the table has the most confident counter").
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Max(pub i64);
+impl Aggregate for Max {
+    fn identity() -> Self { Max(i64::MIN) }
+    fn combine(lhs: Self, rhs: Self) -> Self { Max(lhs.0.max(rhs.0)) }
+}
+impl LazyAction<Max> for RangeAction<i64> {
+    fn identity() -> Self { Self::Add(0) }
+
+    fn apply(&self, agg: Max, _count: usize) -> Max {
+        match self {
+            Self::Assign(v) => Max(*v),
+            Self::Add(v) => if agg.0 == i64::MIN { agg } else { Max(agg.0 + v) },
+        }
+    }
+
+    fn compose(&self, prev: Self) -> Self {
+        match (self, prev) {
+            (Self::Assign(v), _) => Self::Assign(*v),
+            (Self::Add(d), Self::Assign(v)) => Self::Assign(v + d),
+            (Self::Add(d), Self::Add(p)) => Self::Add(p + d),
+        }
+    }
+}
+
+/// A generic lazy-propagation segment tree over `n` leaves, parameterized
+/// over a monoid [`Aggregate`] `A` (e.g. sum or max) and a composable
+/// [`LazyAction`] `L` (e.g. range-assign or range-add).
+///
+/// Backs range operations over predictor tables - periodic decay
+/// (range-add a negative bias), context-switch flushes (range-assign),
+/// and hotspot queries (range-aggregate with a max monoid) - in O(log n)
+/// instead of a full O(n) scan.
+///
+/// Non-power-of-two leaf counts are padded with identity leaves, like
+/// [`HitMissTree`].
+pub struct LazySegTree<A: Aggregate, L: LazyAction<A>> {
+    len: usize,
+    size: usize,
+    agg: Vec<A>,
+    lazy: Vec<Option<L>>,
+}
+impl<A: Aggregate, L: LazyAction<A>> LazySegTree<A, L> {
+    /// Build a tree from an initial sequence of leaf aggregates.
+    pub fn new(leaves: &[A]) -> Self {
+        let len = leaves.len();
+        let size = len.next_power_of_two().max(1);
+        let mut agg = vec![A::identity(); 2 * size];
+        let lazy = vec![None; 2 * size];
+
+        agg[size..size + len].copy_from_slice(leaves);
+        for i in (1..size).rev() {
+            agg[i] = A::combine(agg[2 * i], agg[2 * i + 1]);
+        }
+
+        Self { len, size, agg, lazy }
+    }
+
+    /// Number of leaves (before padding to a power of two).
+    pub fn len(&self) -> usize { self.len }
+
+    fn apply(&mut self, idx: usize, count: usize, action: L) {
+        self.agg[idx] = action.apply(self.agg[idx], count);
+        self.lazy[idx] = Some(match self.lazy[idx] {
+            Some(prev) => action.compose(prev),
+            None => action,
+        });
+    }
+
+    fn push_down(&mut self, idx: usize, count: usize) {
+        if let Some(action) = self.lazy[idx].take() {
+            self.apply(2 * idx, count / 2, action);
+            self.apply(2 * idx + 1, count / 2, action);
+        }
+    }
+
+    /// Apply `action` to every leaf in `[l, r)`.
+    pub fn apply_range(&mut self, l: usize, r: usize, action: L) {
+        self.apply_range_rec(1, 0, self.size, l, r, action);
+    }
+
+    fn apply_range_rec(&mut self, idx: usize, lo: usize, hi: usize, l: usize, r: usize, action: L) {
+        if r <= lo || hi <= l || l >= r {
+            return;
+        }
+        if l <= lo && hi <= r {
+            self.apply(idx, hi - lo, action);
+            return;
+        }
+        self.push_down(idx, hi - lo);
+        let mid = (lo + hi) / 2;
+        self.apply_range_rec(2 * idx, lo, mid, l, r, action);
+        self.apply_range_rec(2 * idx + 1, mid, hi, l, r, action);
+        self.agg[idx] = A::combine(self.agg[2 * idx], self.agg[2 * idx + 1]);
+    }
+
+    /// Return the combined aggregate over `[l, r)`, or [`Aggregate::identity`]
+    /// if the range is empty.
+    pub fn query(&mut self, l: usize, r: usize) -> A {
+        self.query_rec(1, 0, self.size, l, r)
+    }
+
+    fn query_rec(&mut self, idx: usize, lo: usize, hi: usize, l: usize, r: usize) -> A {
+        if r <= lo || hi <= l || l >= r {
+            return A::identity();
+        }
+        if l <= lo && hi <= r {
+            return self.agg[idx];
+        }
+        self.push_down(idx, hi - lo);
+        let mid = (lo + hi) / 2;
+        let left = self.query_rec(2 * idx, lo, mid, l, r);
+        let right = self.query_rec(2 * idx + 1, mid, hi, l, r);
+        A::combine(left, right)
+    }
+}
+
+#[cfg(test)]
+mod lazy_seg_tree_test {
+    use super::*;
+
+    #[test]
+    fn range_add_sums_match_a_linear_scan() {
+        let leaves: Vec<i64> = (0..8).collect();
+        let mut tree: LazySegTree<i64, RangeAction<i64>> = LazySegTree::new(&leaves);
+        assert_eq!(tree.query(0, 8), leaves.iter().sum::<i64>());
+
+        tree.apply_range(2, 5, RangeAction::Add(10));
+        let mut expect = leaves.clone();
+        for v in &mut expect[2..5] { *v += 10; }
+        for l in 0..expect.len() {
+            for r in l..=expect.len() {
+                assert_eq!(tree.query(l, r), expect[l..r].iter().sum::<i64>(), "query({l}, {r})");
+            }
+        }
+    }
+
+    #[test]
+    fn range_assign_overrides_a_prior_add_within_its_span() {
+        let leaves = vec![1i64; 8];
+        let mut tree: LazySegTree<i64, RangeAction<i64>> = LazySegTree::new(&leaves);
+
+        tree.apply_range(0, 8, RangeAction::Add(5));
+        tree.apply_range(2, 6, RangeAction::Assign(0));
+
+        assert_eq!(tree.query(2, 6), 0);
+        assert_eq!(tree.query(0, 2), 12);
+        assert_eq!(tree.query(6, 8), 12);
+    }
+
+    #[test]
+    fn max_aggregate_finds_the_hottest_region() {
+        let leaves: Vec<Max> = [3i64, 1, 4, 1, 5, 9, 2, 6].into_iter().map(Max).collect();
+        let mut tree: LazySegTree<Max, RangeAction<i64>> = LazySegTree::new(&leaves);
+        assert_eq!(tree.query(0, leaves.len()), Max(9));
+        assert_eq!(tree.query(0, 3), Max(4));
+        assert_eq!(tree.query(6, 8), Max(6));
+
+        tree.apply_range(5, 6, RangeAction::Assign(-1));
+        assert_eq!(tree.query(0, leaves.len()), Max(6));
+    }
+}
@@ -8,9 +8,11 @@ use crate::branch::*;
 use crate::analysis::*;
 
 /// Container for recording simple statistics while iterating over a trace.
-pub struct BranchStats {
-    /// Per-branch data (indexed by program counter value)
-    pub data: BTreeMap<usize, BranchData>,
+pub struct TraceStats {
+    /// Per-branch data (indexed by program counter value). Backed by
+    /// [IntMap] rather than a general hash map since PCs are dense-ish
+    /// integer keys and `get_mut` is on the hot path for every record.
+    pub data: IntMap<BranchData>,
 
     /// Number of correct predictions
     pub global_hits: usize,
@@ -18,10 +20,10 @@ pub struct BranchStats {
     /// Number of times any branch instruction was executed
     pub global_brns: usize,
 }
-impl BranchStats {
+impl TraceStats {
     pub fn new() -> Self {
         Self {
-            data: BTreeMap::new(),
+            data: IntMap::new(),
             global_hits: 0,
             global_brns: 0,
         }
@@ -61,13 +63,13 @@ impl BranchStats {
 
     /// Returns a reference to data collected for a particular branch.
     pub fn get(&self, pc: usize) -> Option<&BranchData> {
-        self.data.get(&pc)
+        self.data.get(pc)
     }
 
     /// Returns a mutable reference to data collected for a particular branch.
     /// Creates a new entry if one doesn't already exist.
     pub fn get_mut(&mut self, pc: usize) -> &mut BranchData {
-        self.data.entry(pc).or_insert(BranchData::new())
+        self.data.entry_or_insert_with(pc, BranchData::new)
     }
 
     /// Returns the number of unique observed branch instructions.
@@ -108,10 +110,63 @@ impl BranchStats {
             .sorted_by(|x, y| { x.1.occ.partial_cmp(&y.1.occ).unwrap() })
             .rev()
             .take(n);
-        let res: Vec<(usize, &BranchData)> = iter.map(|(pc, s)| (*pc,s))
-            .collect();
+        let res: Vec<(usize, &BranchData)> = iter.collect();
         res
     }
+
+    /// Like [`TraceStats::get_low_rate_branches`], but scoped to an
+    /// occurrence window `[window.start, window.end)` within each
+    /// branch's own outcome history, using a [`WaveletMatrix`] to count
+    /// taken outcomes in the window without rescanning it.
+    ///
+    /// Unlike [`TraceStats::get_low_rate_branches`], this reports a
+    /// windowed *taken-ratio* rather than a hit-rate: per-occurrence
+    /// prediction correctness isn't retained anywhere in [`BranchData`],
+    /// only the outcomes themselves.
+    pub fn get_low_taken_ratio_branches_windowed(&self,
+        n: usize, window: std::ops::Range<usize>)
+        -> Vec<(usize, f64)>
+    {
+        let window_len = window.end - window.start;
+        let iter = self.data.iter()
+            .filter(|(_, s)| s.outcomes.len() >= window.end)
+            .filter_map(|(pc, s)| {
+                let wm = WaveletMatrix::from_outcomes(&s.outcomes.into_outcomes());
+                let taken = wm.range_freq(window.clone(), Outcome::T as usize);
+                let taken_ratio = taken as f64 / window_len as f64;
+                if taken_ratio <= 0.55 { Some((pc, taken_ratio)) } else { None }
+            })
+            .sorted_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+            .take(n);
+        iter.collect()
+    }
+
+    /// Group branches by [`BranchOutcomes::fingerprint`], so that all
+    /// branches with an identical outcome history end up in the same
+    /// bucket. Useful for reporting redundancy in a workload (e.g.
+    /// "these 812 branches are behaviorally identical").
+    pub fn equivalence_classes(&self) -> HashMap<u64, Vec<usize>> {
+        let mut classes: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (pc, data) in self.data.iter() {
+            let fp = data.outcomes.fingerprint();
+            classes.entry(fp).or_insert_with(Vec::new).push(pc);
+        }
+        classes
+    }
+
+    /// Classify every branch with [`BranchData::classify`] and return a
+    /// histogram of how many branches fall into each [`BranchClass`],
+    /// useful for picking which TAGE history lengths a workload actually
+    /// needs (e.g. a workload dominated by [`BranchClass::UniformPattern`]
+    /// branches wants history at least as long as the exploited period).
+    pub fn classify_histogram(&self) -> HashMap<BranchClass, usize> {
+        let mut hist: HashMap<BranchClass, usize> = HashMap::new();
+        for (_, data) in self.data.iter() {
+            let (class, _rle) = data.classify();
+            *hist.entry(class).or_insert(0) += 1;
+        }
+        hist
+    }
 }
 
 
@@ -0,0 +1,435 @@
+//! A wavelet matrix for answering range rank/frequency/quantile queries
+//! over a sequence of integers in O(log(max value)) time.
+
+use bitvec::prelude::*;
+use crate::branch::*;
+use crate::Outcome;
+use std::collections::{ BinaryHeap, BTreeMap };
+
+/// A single bit-plane of a [`WaveletMatrix`], equipped with O(1) rank
+/// support over 64-bit blocks.
+struct WaveletLevel {
+    /// The bit assigned to each element at this level, in the order
+    /// produced by the stable partition of the previous level.
+    bits: BitVec<u64, Lsb0>,
+
+    /// Cumulative popcount of `bits` up to (and not including) the start
+    /// of each 64-bit block, plus a final entry holding the total.
+    block_rank: Vec<usize>,
+
+    /// Number of elements at this level whose bit is zero.
+    zeros: usize,
+}
+impl WaveletLevel {
+    fn new(bits: BitVec<u64, Lsb0>) -> Self {
+        let mut block_rank = Vec::with_capacity(bits.len() / 64 + 1);
+        let mut acc = 0;
+        for block in bits.chunks(64) {
+            block_rank.push(acc);
+            acc += block.count_ones();
+        }
+        block_rank.push(acc);
+        let zeros = bits.len() - acc;
+        Self { bits, block_rank, zeros }
+    }
+
+    /// Number of zero-bits in `bits[0..i)`.
+    fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+
+    /// Number of one-bits in `bits[0..i)`.
+    fn rank1(&self, i: usize) -> usize {
+        if i == 0 {
+            return 0;
+        }
+        let block = i / 64;
+        let rem = i % 64;
+        let base = self.block_rank[block];
+        if rem == 0 {
+            return base;
+        }
+        base + self.bits[block * 64..i].count_ones()
+    }
+}
+
+/// A succinct index over a sequence of `usize` values (each `< 2^bits`)
+/// that supports `access`, `rank`, `range_freq`, and `quantile` queries in
+/// O(bits) time without rescanning the original sequence.
+///
+/// See the wavelet tree/matrix literature (e.g. Claude, Navarro, and
+/// Ordonez) for the underlying technique.
+pub struct WaveletMatrix {
+    levels: Vec<WaveletLevel>,
+    len: usize,
+    bits: usize,
+}
+impl WaveletMatrix {
+    /// Build a [`WaveletMatrix`] over `data`, where every value must fit in
+    /// `bits` bits.
+    pub fn new(data: &[usize], bits: usize) -> Self {
+        let len = data.len();
+        let mut cur: Vec<usize> = data.to_vec();
+        let mut levels = Vec::with_capacity(bits);
+
+        for level in (0..bits).rev() {
+            let mut plane: BitVec<u64, Lsb0> = BitVec::with_capacity(len);
+            for &v in cur.iter() {
+                plane.push((v >> level) & 1 == 1);
+            }
+
+            let mut zeros = Vec::with_capacity(len);
+            let mut ones = Vec::with_capacity(len);
+            for &v in cur.iter() {
+                if (v >> level) & 1 == 0 {
+                    zeros.push(v);
+                } else {
+                    ones.push(v);
+                }
+            }
+            zeros.extend(ones);
+            cur = zeros;
+
+            levels.push(WaveletLevel::new(plane));
+        }
+
+        Self { levels, len, bits }
+    }
+
+    /// Build a [`WaveletMatrix`] over a slice of [`BranchRecord`]s, keyed on
+    /// a caller-chosen field (e.g. `|r| r.pc`, `|r| r.tgt`).
+    pub fn from_records(records: &[BranchRecord], key: impl Fn(&BranchRecord) -> usize)
+        -> Self
+    {
+        let values: Vec<usize> = records.iter().map(key).collect();
+        let max = values.iter().copied().max().unwrap_or(0);
+        let bits = (usize::BITS - max.leading_zeros()).max(1) as usize;
+        Self::new(&values, bits)
+    }
+
+    /// Build a [`WaveletMatrix`] over a slice of [`BranchRecord`]s, keyed on
+    /// a caller-chosen field, after compressing the distinct key values
+    /// into a dense `[0, sigma)` id space (`sigma` = the next power of two
+    /// `>=` the number of distinct values) instead of sizing the matrix off
+    /// the field's raw bit width.
+    ///
+    /// This matters for windowed branch-frequency and working-set queries
+    /// over PC-keyed traces: a handful of distinct branches can be
+    /// scattered across a wide address range, so [`WaveletMatrix::from_records`]
+    /// would pay for every bit of the widest raw PC while this constructor
+    /// only pays for `ceil(log2(sigma))` levels.
+    pub fn from_records_dense(records: &[BranchRecord], key: impl Fn(&BranchRecord) -> usize)
+        -> Self
+    {
+        let raw: Vec<usize> = records.iter().map(key).collect();
+        let mut ids: BTreeMap<usize, usize> = BTreeMap::new();
+        for &v in &raw {
+            let next_id = ids.len();
+            ids.entry(v).or_insert(next_id);
+        }
+        let sigma = ids.len().next_power_of_two();
+        let bits = sigma.trailing_zeros().max(1) as usize;
+        let values: Vec<usize> = raw.iter().map(|v| ids[v]).collect();
+        Self::new(&values, bits)
+    }
+
+    /// Build a [`WaveletMatrix`] over a sequence of branch outcomes, with
+    /// `Outcome::N` and `Outcome::T` packed as a single bit each.
+    ///
+    /// This is the entry point for phase-detection and aliasing analyses
+    /// that want rank/quantile queries directly over an outcome trace
+    /// (e.g. [`BranchOutcomes::into_outcomes`]) rather than over a
+    /// `BranchRecord` field.
+    pub fn from_outcomes(outcomes: &[Outcome]) -> Self {
+        let values: Vec<usize> = outcomes.iter().map(|o| *o as usize).collect();
+        Self::new(&values, 1)
+    }
+
+    /// Build a [`WaveletMatrix`] over a sequence of sliding `k`-bit
+    /// local-history pattern values (the low `k` outcomes observed so far,
+    /// packed MSB-first, matching how a local-history predictor would
+    /// index into its table), one per position in `outcomes`.
+    pub fn from_local_history(outcomes: &[Outcome], k: usize) -> Self {
+        let mask = if k >= usize::BITS as usize { usize::MAX } else { (1 << k) - 1 };
+        let mut history: usize = 0;
+        let values: Vec<usize> = outcomes.iter().map(|o| {
+            history = ((history << 1) | (*o as usize)) & mask;
+            history
+        }).collect();
+        Self::new(&values, k.max(1))
+    }
+
+    pub fn len(&self) -> usize { self.len }
+
+    /// Return the value originally at position `i`.
+    pub fn access(&self, i: usize) -> usize {
+        let mut i = i;
+        let mut value = 0;
+        for (level, wl) in self.levels.iter().enumerate() {
+            let bit = wl.bits[i];
+            value = (value << 1) | (bit as usize);
+            i = if bit { wl.zeros + wl.rank1(i) } else { wl.rank0(i) };
+            let _ = level;
+        }
+        value
+    }
+
+    /// Return the number of occurrences of `value` in `data[0..i)`.
+    pub fn rank(&self, value: usize, i: usize) -> usize {
+        let mut l = 0;
+        let mut r = i;
+        for level in (0..self.bits).rev() {
+            let wl = &self.levels[self.bits - 1 - level];
+            let bit = (value >> level) & 1 == 1;
+            if bit {
+                l = wl.zeros + wl.rank1(l);
+                r = wl.zeros + wl.rank1(r);
+            } else {
+                l = wl.rank0(l);
+                r = wl.rank0(r);
+            }
+        }
+        r - l
+    }
+
+    /// Return the number of occurrences of `value` within `l..r`.
+    pub fn range_freq(&self, range: std::ops::Range<usize>, value: usize) -> usize {
+        self.rank(value, range.end) - self.rank(value, range.start)
+    }
+
+    /// Return the number of elements in `l..r` whose value is in `lo..hi`.
+    pub fn range_freq_range(&self, range: std::ops::Range<usize>, values: std::ops::Range<usize>)
+        -> usize
+    {
+        self.count_lt(range.clone(), values.end) - self.count_lt(range, values.start)
+    }
+
+    /// Return the number of elements in `l..r` strictly less than `x`.
+    pub fn count_lt(&self, range: std::ops::Range<usize>, x: usize) -> usize {
+        if x == 0 {
+            return 0;
+        }
+        if x >= (1usize << self.bits) {
+            return range.end - range.start;
+        }
+        let mut l = range.start;
+        let mut r = range.end;
+        let mut count = 0;
+        for level in (0..self.bits).rev() {
+            let wl = &self.levels[self.bits - 1 - level];
+            let bit = (x >> level) & 1 == 1;
+            if bit {
+                // Every element with a 0 at this level is less than `x`.
+                count += wl.rank0(r) - wl.rank0(l);
+                l = wl.zeros + wl.rank1(l);
+                r = wl.zeros + wl.rank1(r);
+            } else {
+                l = wl.rank0(l);
+                r = wl.rank0(r);
+            }
+        }
+        count
+    }
+
+    /// Return the wavelet-matrix fragments that [`WaveletMatrix::count_lt`]
+    /// sums over to count the elements of `range` with value `< x`: each
+    /// `(depth, index_range)` pair names a contiguous run of bits at
+    /// level `depth` whose elements are already known to fall below `x`,
+    /// without needing to look at any lower level.
+    ///
+    /// The fragments are disjoint and their lengths sum to
+    /// `self.count_lt(range, x)`; callers that keep their own
+    /// per-level prefix-sum table (keyed by the same post-partition
+    /// ordering this matrix uses internally) can sum over these spans to
+    /// answer a query aligned with, but distinct from, the plain element
+    /// count this matrix already provides - e.g. total weight, rather
+    /// than just occurrence count.
+    pub fn spans(&self, range: std::ops::Range<usize>, x: usize) -> Vec<(usize, std::ops::Range<usize>)> {
+        let mut out = Vec::new();
+        if x == 0 {
+            return out;
+        }
+        if x >= (1usize << self.bits) {
+            out.push((0, range));
+            return out;
+        }
+
+        let mut l = range.start;
+        let mut r = range.end;
+        for (depth, level) in (0..self.bits).rev().enumerate() {
+            let wl = &self.levels[self.bits - 1 - level];
+            let bit = (x >> level) & 1 == 1;
+            if bit {
+                let (zl, zr) = (wl.rank0(l), wl.rank0(r));
+                if zr > zl {
+                    out.push((depth, zl..zr));
+                }
+                l = wl.zeros + wl.rank1(l);
+                r = wl.zeros + wl.rank1(r);
+            } else {
+                l = wl.rank0(l);
+                r = wl.rank0(r);
+            }
+        }
+        out
+    }
+
+    /// Return the `k`-th smallest value (0-indexed) within `l..r`, or
+    /// `None` if the window is empty or `k` is out of range.
+    pub fn quantile(&self, k: usize, range: std::ops::Range<usize>) -> Option<usize> {
+        let mut l = range.start;
+        let mut r = range.end;
+        if l >= r || k >= (r - l) {
+            return None;
+        }
+        let mut k = k;
+        let mut value = 0;
+        for wl in self.levels.iter() {
+            let zeros = wl.rank0(r) - wl.rank0(l);
+            if k < zeros {
+                l = wl.rank0(l);
+                r = wl.rank0(r);
+                value <<= 1;
+            } else {
+                k -= zeros;
+                l = wl.zeros + wl.rank1(l);
+                r = wl.zeros + wl.rank1(r);
+                value = (value << 1) | 1;
+            }
+        }
+        Some(value)
+    }
+
+    /// Return the number of distinct values within `l..r`.
+    ///
+    /// Recursively splits the window into its zero- and one-child ranges
+    /// at each level, only descending into children that are non-empty.
+    /// This costs `O(distinct_count * bits)` rather than the `O(bits)` of
+    /// the other queries, but still avoids collecting or sorting the
+    /// window's values.
+    pub fn count_distinct(&self, range: std::ops::Range<usize>) -> usize {
+        self.count_distinct_at(0, range)
+    }
+
+    fn count_distinct_at(&self, depth: usize, range: std::ops::Range<usize>) -> usize {
+        if range.start >= range.end {
+            return 0;
+        }
+        if depth == self.bits {
+            return 1;
+        }
+        let wl = &self.levels[depth];
+        let (l, r) = (range.start, range.end);
+        let (zl, zr) = (wl.rank0(l), wl.rank0(r));
+        let (ol, or_) = (wl.zeros + wl.rank1(l), wl.zeros + wl.rank1(r));
+        let mut count = 0;
+        if zr > zl {
+            count += self.count_distinct_at(depth + 1, zl..zr);
+        }
+        if or_ > ol {
+            count += self.count_distinct_at(depth + 1, ol..or_);
+        }
+        count
+    }
+
+    /// Return the `k`-th most frequent value within `l..r` as `(value,
+    /// count)` (0-indexed: `k == 0` is the single most frequent value),
+    /// or `None` if the window holds fewer than `k + 1` distinct values.
+    ///
+    /// Descends best-first along whichever node currently holds the
+    /// largest window, using a max-heap over `(window size, depth,
+    /// value prefix, range)` so nodes that can never beat the current
+    /// candidates are never expanded - the standard wavelet-tree top-k
+    /// algorithm (Gagie, Navarro, Puglisi).
+    pub fn topk(&self, range: std::ops::Range<usize>, k: usize) -> Option<(usize, usize)> {
+        if range.start >= range.end {
+            return None;
+        }
+
+        // (window size, depth, value prefix so far, l, r); BinaryHeap is
+        // a max-heap over the tuple, so the largest window always pops
+        // first.
+        let mut heap: BinaryHeap<(usize, usize, usize, usize, usize)> = BinaryHeap::new();
+        heap.push((range.end - range.start, 0, 0, range.start, range.end));
+        let mut remaining = k;
+
+        while let Some((size, depth, value, l, r)) = heap.pop() {
+            if depth == self.bits {
+                if remaining == 0 {
+                    return Some((value, size));
+                }
+                remaining -= 1;
+                continue;
+            }
+            let wl = &self.levels[depth];
+            let (zl, zr) = (wl.rank0(l), wl.rank0(r));
+            if zr > zl {
+                heap.push((zr - zl, depth + 1, value << 1, zl, zr));
+            }
+            let (ol, or_) = (wl.zeros + wl.rank1(l), wl.zeros + wl.rank1(r));
+            if or_ > ol {
+                heap.push((or_ - ol, depth + 1, (value << 1) | 1, ol, or_));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn access_rank_and_range_freq_match_a_linear_scan() {
+        let data = [3usize, 1, 4, 1, 5, 9, 2, 6, 1, 1];
+        let wm = WaveletMatrix::new(&data, 4);
+
+        for (i, &v) in data.iter().enumerate() {
+            assert_eq!(wm.access(i), v);
+        }
+
+        for value in 0..16 {
+            for i in 0..=data.len() {
+                let expect = data[..i].iter().filter(|&&x| x == value).count();
+                assert_eq!(wm.rank(value, i), expect, "rank({value}, {i})");
+            }
+        }
+
+        let range = 2..8;
+        let expect = data[range.clone()].iter().filter(|&&x| x == 1).count();
+        assert_eq!(wm.range_freq(range, 1), expect);
+    }
+
+    #[test]
+    fn quantile_matches_a_sorted_window() {
+        let data = [3usize, 1, 4, 1, 5, 9, 2, 6];
+        let wm = WaveletMatrix::new(&data, 4);
+
+        let range = 1..6;
+        let mut sorted: Vec<usize> = data[range.clone()].to_vec();
+        sorted.sort();
+        for (k, &expect) in sorted.iter().enumerate() {
+            assert_eq!(wm.quantile(k, range.clone()), Some(expect));
+        }
+        assert_eq!(wm.quantile(sorted.len(), range), None);
+    }
+
+    #[test]
+    fn empty_range_queries_return_identity_results() {
+        let data = [0usize, 1, 2, 3];
+        let wm = WaveletMatrix::new(&data, 2);
+        assert_eq!(wm.range_freq(2..2, 1), 0);
+        assert_eq!(wm.quantile(0, 2..2), None);
+        assert_eq!(wm.count_distinct(2..2), 0);
+    }
+
+    #[test]
+    fn from_outcomes_round_trips_through_access() {
+        let outcomes = [Outcome::T, Outcome::N, Outcome::N, Outcome::T, Outcome::T];
+        let wm = WaveletMatrix::from_outcomes(&outcomes);
+        for (i, o) in outcomes.iter().enumerate() {
+            assert_eq!(wm.access(i), *o as usize);
+        }
+        assert_eq!(wm.range_freq(0..outcomes.len(), Outcome::T as usize), 3);
+    }
+}
@@ -22,7 +22,7 @@ fn analyze_branches(trace: &BinaryTrace) {
         x.1.outcomes.len().partial_cmp(&y.1.outcomes.len()).unwrap()
     })
     {
-        let class = brn.classify();
+        let (class, _rle) = brn.classify();
         if class == BranchClass::Unknown {
             unk_brns.push((pc, brn));
         }
@@ -93,7 +93,10 @@ fn main() {
         return;
     }
 
-    let trace = BinaryTrace::from_file(&args[1], "");
+    let trace = match BinaryTrace::from_file(&args[1], "") {
+        Ok(trace) => trace,
+        Err(e) => { eprintln!("[!] couldn't load '{}': {}", args[1], e); return; },
+    };
     println!("[*] Loaded {} records from '{}'", trace.as_slice().len(), args[1]);
     analyze_branches(&trace);
 }
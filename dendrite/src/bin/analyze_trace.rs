@@ -32,7 +32,7 @@ fn main() {
         x.1.outcomes.len().partial_cmp(&y.1.outcomes.len()).unwrap()
     })
     {
-        let class = brn.classify();
+        let (class, _rle) = brn.classify();
         if class == BranchClass::Unknown {
             unk_brns.push((pc, brn));
         }
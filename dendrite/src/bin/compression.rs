@@ -204,7 +204,10 @@ fn main() {
         return;
     }
 
-    let trace = BinaryTrace::from_file(&args[1], "");
+    let trace = match BinaryTrace::from_file(&args[1], "") {
+        Ok(trace) => trace,
+        Err(e) => { eprintln!("[!] couldn't load '{}': {}", args[1], e); return; },
+    };
     let trace_records = trace.as_slice();
     println!("[*] Loaded {} records from {}", trace.num_entries(), args[1]);
 
@@ -1,5 +1,4 @@
 use dendrite::*;
-use dendrite::stats::*;
 use dendrite::predictor::simple;
 use std::env;
 use std::collections::VecDeque;
@@ -107,6 +106,10 @@ fn main() {
 
     let traces = BinaryTraceSet::new_from_slice(&args[1..]);
     for trace in traces {
+        let trace = match trace {
+            Ok(trace) => trace,
+            Err(e) => { eprintln!("[!] skipping trace: {}", e); continue; },
+        };
         if trace.num_entries() < 100 { continue; }
         println!("[*] {}", trace.name());
         let records = trace.as_slice();
@@ -1,7 +1,7 @@
 /// Evaluate a [`SimplePredictor`] against one or more traces. 
 
 use dendrite::*;
-use dendrite::stats::*;
+use dendrite::analysis::*;
 use dendrite::predictor::simple;
 use std::env;
 
@@ -30,6 +30,10 @@ fn main() {
     let traces = BinaryTraceSet::new_from_slice(&args[1..]);
 
     for trace in traces {
+        let trace = match trace {
+            Ok(trace) => trace,
+            Err(e) => { eprintln!("[!] skipping trace: {}", e); continue; },
+        };
         if trace.num_entries() < 100 { continue; }
         println!("[*] {}", trace.name());
         let records = trace.as_slice();
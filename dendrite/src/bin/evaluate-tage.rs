@@ -57,7 +57,10 @@ fn main() {
         return;
     }
 
-    let trace = BinaryTrace::from_file(&args[1], "");
+    let trace = match BinaryTrace::from_file(&args[1], "") {
+        Ok(trace) => trace,
+        Err(e) => { eprintln!("[!] couldn't load '{}': {}", args[1], e); return; },
+    };
     let trace_records = trace.as_slice();
     println!("[*] Loaded {} records from {}", trace.num_entries(), args[1]);
 
@@ -79,11 +82,10 @@ fn main() {
     );
     println!("      Global History Register (GHR) length: {} bits", ghr_bits);
 
-    // Randomize the state of global history 
+    // Randomize the state of global history
     for _ in 0..64 {
-        ghr.shift_by(1);
-        ghr.data_mut().set(0, rand::random());
-        tage.update_history(&ghr);
+        let bit = if rand::random() { Outcome::T } else { Outcome::N };
+        tage.update_history(&mut ghr, bit);
     }
 
     let mut hits = 0;
@@ -102,13 +104,12 @@ fn main() {
             BranchKind::DirectCall |
             BranchKind::IndirectCall |
             BranchKind::Return => {
-                ghr.shift_by(1);
-                ghr.data_mut().set(0, true);
-                tage.update_history(&ghr);
+                tage.update_history(&mut ghr, Outcome::T);
             },
 
             // Use the TAGE predictor to evaluate conditional branches
-            BranchKind::DirectBranch => {
+            BranchKind::DirectBranch |
+            BranchKind::IndirectBranch => {
                 // Sample the number of mispredictions every 1000 branches
                 if brns % 1000 == 0 { 
                     mpkb_cnts.push(mpkb_window);
@@ -132,12 +133,10 @@ fn main() {
 
                 tage.update(inputs, prediction, record.outcome());
 
-                // Update the global history register. 
-                // Use the GHR to update the folded history registers in 
-                // each of the tagged components. 
-                ghr.shift_by(1);
-                ghr.data_mut().set(0, record.outcome().into());
-                tage.update_history(&ghr);
+                // Speculatively insert the resolved outcome into the
+                // global history register, and propagate the update into
+                // the folded history registers of each tagged component.
+                tage.update_history(&mut ghr, record.outcome());
             },
         }
     }
@@ -11,13 +11,27 @@ fn main() {
         return;
     }
 
-    let trace = BinaryTrace::from_file(&args[1], "");
+    let trace = match BinaryTrace::from_file(&args[1], "") {
+        Ok(trace) => trace,
+        Err(e) => { eprintln!("[!] couldn't load '{}': {}", args[1], e); return; },
+    };
     let trace_records = trace.as_slice();
     println!("[*] Loaded {} records from {}", trace.num_entries(), args[1]);
     for record in trace_records {
-        println!("{:016x} {:016x} {:?} {:?}", 
-            record.pc, record.tgt, record.outcome, record.kind);
+        println!("{:016x} {:016x} {:?} {:?}",
+            record.pc, record.tgt, record.outcome(), record.kind());
     }
 
-
+    // Windowed queries over the first half of the trace, answered without
+    // rescanning it.
+    if trace.num_entries() > 0 {
+        let mid = trace.num_entries() / 2;
+        let index = TraceIndex::build(&trace);
+        println!("[*] Distinct branch sites in [0, {}): {}",
+            mid, index.num_distinct_pcs(0..mid));
+        if let Some((target, count)) = index.kth_most_frequent_target(0..mid, 0) {
+            println!("[*] Most frequent target in [0, {}): {:016x} ({} times)",
+                mid, target, count);
+        }
+    }
 }
@@ -1,7 +1,9 @@
-//! Types for representing branches and branch outcomes. 
+//! Types for representing branches and branch outcomes.
 
 use std::collections::*;
 use bitvec::prelude::*;
+use bitflags::bitflags;
+use serde::{ Serialize, Deserialize };
 
 /// A branch outcome. 
 #[repr(u32)]
@@ -70,81 +72,223 @@ impl Into<bool> for Outcome {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BranchKind {
     /// A direct conditional branch instruction.
-    DirectBranch = BranchFlags::BRN_FLAG,
+    DirectBranch = BranchFlags::BRN.bits(),
 
     /// A direct unconditional jump instruction.
-    DirectJump   = BranchFlags::JMP_FLAG,
+    DirectJump   = BranchFlags::JMP.bits(),
 
     /// An indirect unconditional jump instruction.
-    IndirectJump = BranchFlags::JMP_FLAG | BranchFlags::IND_FLAG,
+    IndirectJump = BranchFlags::JMP.bits() | BranchFlags::IND.bits(),
+
+    /// An indirect conditional branch instruction (e.g. a multi-way
+    /// switch dispatched through a jump table).
+    IndirectBranch = BranchFlags::BRN.bits() | BranchFlags::IND.bits(),
 
     /// A direct procedure call instruction.
-    DirectCall   = BranchFlags::CALL_FLAG,
+    DirectCall   = BranchFlags::CALL.bits(),
 
     /// An indirect procedure call instruction.
-    IndirectCall = BranchFlags::CALL_FLAG | BranchFlags::IND_FLAG,
+    IndirectCall = BranchFlags::CALL.bits() | BranchFlags::IND.bits(),
 
     /// A return instruction.
-    Return       = BranchFlags::RET_FLAG | BranchFlags::IND_FLAG,
-}
-impl BranchKind { 
-    const DIRECT_BRANCH: u32 = BranchFlags::BRN_FLAG;
-    const DIRECT_JUMP: u32 = BranchFlags::JMP_FLAG;
-    const DIRECT_CALL: u32 = BranchFlags::CALL_FLAG;
-    const INDIRECT_CALL: u32 = BranchFlags::CALL_FLAG | BranchFlags::IND_FLAG;
-    const INDIRECT_JUMP: u32 = BranchFlags::JMP_FLAG | BranchFlags::IND_FLAG;
-    const RETURN: u32 = BranchFlags::RET_FLAG | BranchFlags::IND_FLAG;
-}
-impl From<u32> for BranchKind { 
-    fn from(x: u32) -> Self { 
-        match x & 0b01_1111 { 
-            Self::DIRECT_BRANCH => Self::DirectBranch,
-            Self::DIRECT_JUMP   => Self::DirectJump,
-            Self::DIRECT_CALL   => Self::DirectCall,
-            Self::INDIRECT_JUMP => Self::IndirectJump,
-            Self::INDIRECT_CALL => Self::IndirectCall,
-            Self::RETURN        => Self::Return,
-            _ => unimplemented!("invalid flags? ({:05b})", x & 0b1_1111),
+    Return       = BranchFlags::RET.bits() | BranchFlags::IND.bits(),
+}
+impl BranchKind {
+    const DIRECT_BRANCH: u32 = BranchFlags::BRN.bits();
+    const DIRECT_JUMP: u32 = BranchFlags::JMP.bits();
+    const DIRECT_CALL: u32 = BranchFlags::CALL.bits();
+    const INDIRECT_CALL: u32 = BranchFlags::CALL.bits() | BranchFlags::IND.bits();
+    const INDIRECT_JUMP: u32 = BranchFlags::JMP.bits() | BranchFlags::IND.bits();
+    const INDIRECT_BRANCH: u32 = BranchFlags::BRN.bits() | BranchFlags::IND.bits();
+    const RETURN: u32 = BranchFlags::RET.bits() | BranchFlags::IND.bits();
+}
+/// An error produced when a raw `u32` (e.g. one read off the DynamoRIO FFI
+/// boundary, or decoded from a trace file) doesn't correspond to valid
+/// [BranchFlags]/[BranchKind] state.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BranchFlagsError {
+    /// The low 5 bits (`BRN`/`JMP`/`CALL`/`RET`/`IND`) don't form any of
+    /// the combinations [BranchKind] enumerates - e.g. both `BRN` and
+    /// `RET` set, or none of them.
+    InvalidKind(u32),
+
+    /// Bits outside of the named [BranchFlags] constants were set.
+    UnknownBits(u32),
+}
+impl std::fmt::Display for BranchFlagsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::InvalidKind(bits) =>
+                write!(f, "flags {:05b} don't correspond to any known BranchKind", bits),
+            Self::UnknownBits(bits) =>
+                write!(f, "flags contain unrecognized bits ({:#010x})", bits),
+        }
+    }
+}
+impl std::error::Error for BranchFlagsError {}
+
+impl TryFrom<u32> for BranchKind {
+    type Error = BranchFlagsError;
+    fn try_from(x: u32) -> Result<Self, Self::Error> {
+        match x & 0b01_1111 {
+            Self::DIRECT_BRANCH   => Ok(Self::DirectBranch),
+            Self::DIRECT_JUMP     => Ok(Self::DirectJump),
+            Self::DIRECT_CALL     => Ok(Self::DirectCall),
+            Self::INDIRECT_JUMP   => Ok(Self::IndirectJump),
+            Self::INDIRECT_BRANCH => Ok(Self::IndirectBranch),
+            Self::INDIRECT_CALL   => Ok(Self::IndirectCall),
+            Self::RETURN          => Ok(Self::Return),
+            other => Err(BranchFlagsError::InvalidKind(other)),
         }
     }
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct BranchFlags(pub u32);
-impl BranchFlags { 
+/// The origin of an indirect branch's target address.
+///
+/// NOTE: This is kept in-sync *manually* with headers in the DynamoRIO
+/// client (see `./dynamorio/src/dendrite.h`), same as [`BranchKind`].
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum IndirectSource {
+    /// Target came from a general-purpose register.
+    Register = 1,
+
+    /// Target came from a memory load (e.g. jump-table or vtable
+    /// dispatch).
+    Memory = 2,
+
+    /// Target came from a link or count register (e.g. PowerPC's `lr`/
+    /// `ctr`, used for return-style and loop-style indirection).
+    LinkRegister = 3,
+}
+impl IndirectSource {
+    fn from_bits(bits: u32) -> Option<Self> {
+        match bits {
+            1 => Some(Self::Register),
+            2 => Some(Self::Memory),
+            3 => Some(Self::LinkRegister),
+            _ => None,
+        }
+    }
+}
 
-    const BRN_FLAG: u32   = (1 << 0);
-    const JMP_FLAG: u32   = (1 << 1);
-    const CALL_FLAG: u32  = (1 << 2);
-    const RET_FLAG: u32   = (1 << 3);
-    const IND_FLAG: u32   = (1 << 4);
-    const TAKEN_FLAG: u32 = (1 << 5);
+bitflags! {
+    /// Bit-packed flags describing a branch: its [`BranchKind`], its
+    /// resolved [`Outcome`], delay-slot/annulling behavior, the origin of
+    /// an indirect target, and its instruction length, all in a single
+    /// `u32` so a [`BranchRecord`] stays as small as the DynamoRIO client's
+    /// own record.
+    ///
+    /// NOTE: This is kept in-sync *manually* with headers in the
+    /// DynamoRIO client (see `./dynamorio/src/dendrite.h`).
+    #[repr(transparent)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    pub struct BranchFlags: u32 {
+        const BRN   = 1 << 0;
+        const JMP   = 1 << 1;
+        const CALL  = 1 << 2;
+        const RET   = 1 << 3;
+        const IND   = 1 << 4;
+        const TAKEN = 1 << 5;
+
+        /// Set on architectures (SPARC, MIPS) where the instruction at
+        /// `pc + ilen` is architecturally executed after this branch
+        /// resolves.
+        const DELAY_SLOT = 1 << 6;
+
+        /// Set when the delay-slot instruction is squashed according to
+        /// this branch's outcome - always squashed for an unconditional
+        /// annulling branch, and squashed only when *not* taken for a
+        /// conditional one. Meaningless unless [`Self::DELAY_SLOT`] is
+        /// also set.
+        const ANNUL = 1 << 7;
+
+        /// 2-bit [`IndirectSource`], meaningless unless [`Self::IND`] is
+        /// also set.
+        const INDIRECT_SRC = 0b11 << 8;
+
+        /// 4-bit instruction length.
+        const ILEN = 0b1111 << 28;
+    }
+}
+impl BranchFlags {
+    const INDIRECT_SRC_SHIFT: u32 = 8;
+    const ILEN_SHIFT: u32 = 28;
 
-    /// 4-bit instruction length
-    const ILEN_MASK: u32   = 0b1111_0000_0000_0000_0000_0000_0000_0000;
+    pub fn ilen(&self) -> usize {
+        ((self.bits() & Self::ILEN.bits()) >> Self::ILEN_SHIFT) as usize
+    }
 
-    pub fn ilen(&self) -> usize { 
-        ((self.0 & Self::ILEN_MASK) >> 28) as usize
+    pub fn is_brn(&self) -> bool { self.contains(Self::BRN) }
+    pub fn is_jmp(&self) -> bool { self.contains(Self::JMP) }
+    pub fn is_call(&self) -> bool { self.contains(Self::CALL) }
+    pub fn is_ret(&self) -> bool { self.contains(Self::RET) }
+    pub fn is_direct(&self) -> bool { !self.contains(Self::IND) }
+    pub fn is_indirect(&self) -> bool { self.contains(Self::IND) }
+    pub fn is_taken(&self) -> bool { self.contains(Self::TAKEN) }
+    pub fn has_delay_slot(&self) -> bool { self.contains(Self::DELAY_SLOT) }
+    pub fn is_annulling(&self) -> bool { self.contains(Self::ANNUL) }
+
+    /// Return the origin of this branch's target address, or `None` if
+    /// it's direct (or the source was never recorded).
+    pub fn indirect_source(&self) -> Option<IndirectSource> {
+        let bits = (self.bits() & Self::INDIRECT_SRC.bits()) >> Self::INDIRECT_SRC_SHIFT;
+        IndirectSource::from_bits(bits)
+    }
+
+    /// Returns 'true' if this branch's target came from a memory load
+    /// (jump-table / vtable dispatch).
+    pub fn is_jump_table(&self) -> bool {
+        matches!(self.indirect_source(), Some(IndirectSource::Memory))
     }
 
-    pub fn is_brn(&self) -> bool { self.0 & Self::BRN_FLAG != 0 }
-    pub fn is_jmp(&self) -> bool { self.0 & Self::JMP_FLAG != 0 }
-    pub fn is_call(&self) -> bool { self.0 & Self::CALL_FLAG != 0 }
-    pub fn is_ret(&self) -> bool { self.0 & Self::RET_FLAG != 0 }
-    pub fn is_direct(&self) -> bool { self.0 & Self::IND_FLAG == 0 }
-    pub fn is_indirect(&self) -> bool { self.0 & Self::IND_FLAG != 0 }
-    pub fn is_taken(&self) -> bool { self.0 & Self::TAKEN_FLAG != 0 }
+    /// Returns 'true' if this branch's target came from a general-purpose
+    /// register.
+    pub fn is_register_indirect(&self) -> bool {
+        matches!(self.indirect_source(), Some(IndirectSource::Register))
+    }
 
-    pub fn kind(&self) -> BranchKind { 
-        self.0.try_into().unwrap()
+    /// Fallible version of [`Self::kind`] - the error-surfacing path a
+    /// corrupt trace (e.g. garbage crossing the DynamoRIO FFI boundary)
+    /// should be decoded through instead of panicking.
+    pub fn try_kind(&self) -> Result<BranchKind, BranchFlagsError> {
+        self.bits().try_into()
+    }
+
+    /// Returns this flags' [`BranchKind`].
+    ///
+    /// # Panics
+    /// Panics if the low 5 bits don't correspond to a valid [`BranchKind`]
+    /// - use [`Self::try_kind`] or [`Self::validate`] at trace-ingestion
+    /// boundaries where that isn't already guaranteed.
+    pub fn kind(&self) -> BranchKind {
+        self.try_kind().unwrap()
     }
 
-    pub fn new(kind: BranchKind, outcome: Outcome) -> Self { 
+    pub fn new(kind: BranchKind, outcome: Outcome) -> Self {
         let kbits = kind as u32;
-        Self(kbits)
+        let taken: bool = outcome.into();
+        let tbits = if taken { Self::TAKEN.bits() } else { 0 };
+        Self::from_bits_retain(kbits | tbits)
+    }
+
+    /// Record the origin of this indirect branch's target address.
+    pub fn with_indirect_source(self, src: IndirectSource) -> Self {
+        Self::from_bits_retain(self.bits() | ((src as u32) << Self::INDIRECT_SRC_SHIFT))
     }
 
+    /// Check that these flags are internally consistent: the low 5 bits
+    /// form a valid [`BranchKind`], and no bits outside of the named
+    /// constants above are set. Meant for trace-ingestion boundaries (e.g.
+    /// [`crate::trace::TraceReader`], or data crossing the DynamoRIO FFI
+    /// boundary) so a corrupt trace surfaces an error instead of panicking
+    /// somewhere deep inside a predictor.
+    pub fn validate(&self) -> Result<(), BranchFlagsError> {
+        if Self::from_bits(self.bits()).is_none() {
+            return Err(BranchFlagsError::UnknownBits(self.bits()));
+        }
+        self.try_kind().map(|_| ())
+    }
 }
 
 
@@ -208,10 +352,148 @@ impl BranchRecord {
         self.flags.is_jmp()
     }
 
-    /// Returns 'true' if this is a "call" or "return". 
-    pub fn is_procedural(&self) -> bool { 
+    /// Returns 'true' if this is a "call" or "return".
+    pub fn is_procedural(&self) -> bool {
         self.flags.is_call() || self.flags.is_ret()
     }
+
+    /// Returns 'true' if the instruction at [`Self::delay_slot_pc`] is
+    /// architecturally executed after this branch resolves.
+    pub fn has_delay_slot(&self) -> bool {
+        self.flags.has_delay_slot()
+    }
+
+    /// Returns 'true' if the delay-slot instruction is squashed according
+    /// to this branch's outcome - see [`BranchFlags::ANNUL`].
+    /// Meaningless unless [`Self::has_delay_slot`] is also true.
+    pub fn is_annulling(&self) -> bool {
+        self.flags.is_annulling()
+    }
+
+    /// Address of this branch's delay-slot instruction, if any.
+    pub fn delay_slot_pc(&self) -> usize {
+        self.pc + self.ilen()
+    }
+
+    /// Return the origin of this branch's target address, or `None` if
+    /// it's direct (or the source was never recorded).
+    pub fn indirect_source(&self) -> Option<IndirectSource> {
+        self.flags.indirect_source()
+    }
+
+    /// Returns 'true' if this branch's target came from a memory load
+    /// (jump-table / vtable dispatch).
+    pub fn is_jump_table(&self) -> bool {
+        self.flags.is_jump_table()
+    }
+
+    /// Returns 'true' if this branch's target came from a general-purpose
+    /// register.
+    pub fn is_register_indirect(&self) -> bool {
+        self.flags.is_register_indirect()
+    }
+
+    /// Returns 'true' if the delay-slot instruction actually executes,
+    /// given this branch resolved to `outcome`.
+    ///
+    /// Always `true` when there's no delay slot to annul. Otherwise: an
+    /// unconditional annulling branch always squashes its delay slot, and
+    /// a conditional annulling branch only squashes it when not taken.
+    pub fn delay_slot_executes(&self, outcome: Outcome) -> bool {
+        if !self.has_delay_slot() || !self.is_annulling() {
+            return true;
+        }
+        if self.is_conditional() {
+            outcome == Outcome::T
+        } else {
+            false
+        }
+    }
+
+    /// Check that this record's flags are internally consistent - see
+    /// [`BranchFlags::validate`].
+    pub fn validate(&self) -> Result<(), BranchFlagsError> {
+        self.flags.validate()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn every_branch_kind_round_trips_through_flags() {
+        let kinds = [
+            BranchKind::DirectBranch,
+            BranchKind::DirectJump,
+            BranchKind::IndirectJump,
+            BranchKind::IndirectBranch,
+            BranchKind::DirectCall,
+            BranchKind::IndirectCall,
+            BranchKind::Return,
+        ];
+        for kind in kinds {
+            let flags = BranchFlags::new(kind, Outcome::T);
+            assert_eq!(flags.kind(), kind);
+            assert_eq!(flags.try_kind(), Ok(kind));
+            assert!(flags.validate().is_ok());
+            assert!(flags.is_taken());
+
+            let flags = BranchFlags::new(kind, Outcome::N);
+            assert_eq!(flags.kind(), kind);
+            assert!(!flags.is_taken());
+        }
+    }
+
+    #[test]
+    fn invalid_low_bits_are_rejected_by_try_kind_and_validate() {
+        // BRN and RET both set is not any valid BranchKind.
+        let flags = BranchFlags::from_bits_retain(BranchFlags::BRN.bits() | BranchFlags::RET.bits());
+        assert_eq!(flags.try_kind(), Err(BranchFlagsError::InvalidKind(0b01001)));
+        assert_eq!(flags.validate(), Err(BranchFlagsError::InvalidKind(0b01001)));
+
+        // None of BRN/JMP/CALL/RET set is also invalid.
+        let flags = BranchFlags::from_bits_retain(0);
+        assert!(flags.try_kind().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn kind_panics_on_invalid_flags() {
+        let flags = BranchFlags::from_bits_retain(BranchFlags::BRN.bits() | BranchFlags::RET.bits());
+        let _ = flags.kind();
+    }
+
+    #[test]
+    fn unknown_bits_are_rejected_by_validate_but_not_try_kind() {
+        let valid = BranchFlags::new(BranchKind::DirectJump, Outcome::T);
+        let with_garbage = BranchFlags::from_bits_retain(valid.bits() | (1 << 27));
+        assert!(with_garbage.try_kind().is_ok());
+        assert_eq!(with_garbage.validate(), Err(BranchFlagsError::UnknownBits(with_garbage.bits())));
+    }
+
+    #[test]
+    fn indirect_source_round_trips_and_is_none_for_direct_branches() {
+        let direct = BranchFlags::new(BranchKind::DirectJump, Outcome::T);
+        assert_eq!(direct.indirect_source(), None);
+
+        let indirect = BranchFlags::new(BranchKind::IndirectJump, Outcome::T)
+            .with_indirect_source(IndirectSource::Memory);
+        assert_eq!(indirect.indirect_source(), Some(IndirectSource::Memory));
+        assert!(indirect.is_jump_table());
+        assert!(!indirect.is_register_indirect());
+    }
+
+    #[test]
+    fn branch_record_validate_delegates_to_flags() {
+        let flags = BranchFlags::new(BranchKind::DirectBranch, Outcome::T);
+        let record = BranchRecord { pc: 0x1000, tgt: 0x2000, flags };
+        assert!(record.validate().is_ok());
+
+        let bad_flags = BranchFlags::from_bits_retain(BranchFlags::BRN.bits() | BranchFlags::RET.bits());
+        let bad_record = BranchRecord { pc: 0x1000, tgt: 0x2000, flags: bad_flags };
+        assert!(bad_record.validate().is_err());
+    }
 }
 
 
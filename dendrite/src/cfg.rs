@@ -0,0 +1,293 @@
+//! Basic-block control-flow-graph reconstruction from a [BranchRecord] stream.
+//!
+//! The input is a trace of *branches only* (as produced by the DynamoRIO
+//! client), not every instruction, so a block's start can't be read
+//! directly off the trace - it's inferred the same way a MIR `Body` is
+//! built: every branch target and every fallthrough address (`pc + ilen`)
+//! is a candidate block "leader", and a block spans from the nearest known
+//! leader up to (and including) the next branching instruction.
+
+use std::collections::{ BTreeSet, HashMap };
+use crate::branch::*;
+
+/// A single outgoing edge from a [BasicBlock], with an execution-frequency
+/// counter.
+#[derive(Clone, Debug)]
+pub struct CfgEdge {
+    pub target: usize,
+    pub count: usize,
+}
+
+/// How a [BasicBlock] hands control to its successor(s).
+#[derive(Clone, Debug)]
+pub enum Terminator {
+    /// A conditional direct branch: `taken` is reached when the branch
+    /// resolves to [Outcome::T], `not_taken` when it resolves to
+    /// [Outcome::N].
+    Conditional { taken: CfgEdge, not_taken: CfgEdge },
+
+    /// An unconditional direct jump/call: a single successor.
+    Direct(CfgEdge),
+
+    /// An indirect jump/call/return: every target observed at this site,
+    /// each with its own hit count.
+    Indirect(HashMap<usize, usize>),
+
+    /// No branch was ever observed ending this block - it exists only
+    /// because [ControlFlowGraph::split_at] cut it out of a longer block
+    /// whose middle turned out to be a leader. Control always falls
+    /// straight through into the given address.
+    Fallthrough(usize),
+}
+
+/// A basic block: a maximal run of instructions from a leader address up to
+/// and including its terminating branch (or, for a block produced by a
+/// split, up to wherever the split occurred).
+#[derive(Clone, Debug)]
+pub struct BasicBlock {
+    /// Address of the first instruction in the block.
+    pub start: usize,
+
+    /// Address of the last instruction in the block.
+    pub end: usize,
+
+    /// The kind of the terminating branch, or `None` for a [Terminator::Fallthrough]
+    /// block that was never itself observed as a branch.
+    pub kind: Option<BranchKind>,
+
+    /// Outgoing edges, with per-edge execution-frequency counters.
+    pub terminator: Terminator,
+}
+
+/// A basic-block control-flow graph, reconstructed incrementally from an
+/// ordered [BranchRecord] stream.
+pub struct ControlFlowGraph {
+    /// Blocks, keyed by start address.
+    pub blocks: HashMap<usize, BasicBlock>,
+
+    /// Every address known to start a block, kept sorted so the block
+    /// containing a given address can be found by predecessor lookup.
+    leaders: BTreeSet<usize>,
+}
+impl ControlFlowGraph {
+    pub fn new() -> Self {
+        Self { blocks: HashMap::new(), leaders: BTreeSet::new() }
+    }
+
+    /// Reconstruct a CFG from an entire trace.
+    pub fn build(records: &[BranchRecord]) -> Self {
+        let mut cfg = Self::new();
+        for record in records {
+            cfg.observe(record);
+        }
+        cfg
+    }
+
+    /// Find the nearest known leader at or before `pc`, or `pc` itself if
+    /// none is known yet.
+    fn block_start(&self, pc: usize) -> usize {
+        self.leaders.range(..=pc).next_back().copied().unwrap_or(pc)
+    }
+
+    /// Register `addr` as a leader, splitting whichever block currently
+    /// spans across it (if this is the first time it's been seen).
+    fn add_leader(&mut self, addr: usize) {
+        if self.leaders.insert(addr) {
+            self.split_at(addr);
+        }
+    }
+
+    /// If some existing block's range straddles `leader` (i.e. it starts
+    /// before `leader` but ends at or after it), split it into a
+    /// [Terminator::Fallthrough] prefix and a suffix starting at `leader`
+    /// that inherits the original terminator.
+    fn split_at(&mut self, leader: usize) {
+        let Some(&prev) = self.leaders.range(..leader).next_back() else { return; };
+        let Some(old) = self.blocks.remove(&prev) else { return; };
+        if leader > old.end {
+            self.blocks.insert(prev, old);
+            return;
+        }
+
+        let prefix = BasicBlock {
+            start: old.start,
+            end: old.start,
+            kind: None,
+            terminator: Terminator::Fallthrough(leader),
+        };
+        let suffix = BasicBlock {
+            start: leader,
+            end: old.end,
+            kind: old.kind,
+            terminator: old.terminator,
+        };
+        self.blocks.insert(prefix.start, prefix);
+        self.blocks.insert(suffix.start, suffix);
+    }
+
+    /// Fold a single [BranchRecord] into the graph.
+    pub fn observe(&mut self, record: &BranchRecord) {
+        let fallthrough = record.pc + record.ilen();
+
+        // Find (and register) the leader starting the block this branch
+        // terminates, using only leaders already known from earlier
+        // records - `record`'s own target/fallthrough are leaders for
+        // whatever comes *after* it, not for itself.
+        let start = self.block_start(record.pc);
+        self.add_leader(start);
+
+        let block = self.blocks.entry(start).or_insert_with(|| BasicBlock {
+            start,
+            end: record.pc,
+            kind: None,
+            terminator: Terminator::Fallthrough(fallthrough),
+        });
+        block.end = record.pc;
+        block.kind = Some(record.kind());
+
+        match record.kind() {
+            BranchKind::DirectBranch => {
+                if !matches!(block.terminator, Terminator::Conditional { .. }) {
+                    block.terminator = Terminator::Conditional {
+                        taken: CfgEdge { target: record.tgt, count: 0 },
+                        not_taken: CfgEdge { target: fallthrough, count: 0 },
+                    };
+                }
+                if let Terminator::Conditional { taken, not_taken } = &mut block.terminator {
+                    match record.outcome() {
+                        Outcome::T => taken.count += 1,
+                        Outcome::N => not_taken.count += 1,
+                    }
+                }
+            },
+
+            BranchKind::DirectJump | BranchKind::DirectCall => {
+                if !matches!(block.terminator, Terminator::Direct(_)) {
+                    block.terminator = Terminator::Direct(CfgEdge { target: record.tgt, count: 0 });
+                }
+                if let Terminator::Direct(edge) = &mut block.terminator {
+                    edge.count += 1;
+                }
+            },
+
+            BranchKind::IndirectJump | BranchKind::IndirectCall
+            | BranchKind::IndirectBranch | BranchKind::Return => {
+                if !matches!(block.terminator, Terminator::Indirect(_)) {
+                    block.terminator = Terminator::Indirect(HashMap::new());
+                }
+                if let Terminator::Indirect(targets) = &mut block.terminator {
+                    *targets.entry(record.tgt).or_insert(0) += 1;
+                }
+            },
+        }
+
+        // The branch's target and fallthrough are leaders for the blocks
+        // that come after it - except the fallthrough when it's actually a
+        // delay slot: that instruction runs as part of this branch, not as
+        // the entry point of a new block.
+        self.add_leader(record.tgt);
+        if !record.has_delay_slot() {
+            self.add_leader(fallthrough);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Outcome;
+
+    /// Build a [BranchRecord] with a fixed 4-byte instruction length, since
+    /// [BranchFlags] has no public ilen setter outside the DynamoRIO FFI
+    /// boundary.
+    fn record(pc: usize, tgt: usize, kind: BranchKind, outcome: Outcome) -> BranchRecord {
+        let flags_bits = BranchFlags::new(kind, outcome).bits() | (4 << 28);
+        BranchRecord { pc, tgt, flags: BranchFlags::from_bits_retain(flags_bits) }
+    }
+
+    /// Like [record], but with a delay slot, so the branch's own
+    /// fallthrough is *not* registered as a leader - used to grow a block
+    /// across more than one branch address for the split test below.
+    fn record_delay_slot(pc: usize, tgt: usize, kind: BranchKind, outcome: Outcome) -> BranchRecord {
+        let flags_bits = BranchFlags::new(kind, outcome).bits()
+            | (4 << 28)
+            | BranchFlags::DELAY_SLOT.bits();
+        BranchRecord { pc, tgt, flags: BranchFlags::from_bits_retain(flags_bits) }
+    }
+
+    #[test]
+    fn straight_line_fallthrough_forms_a_single_block() {
+        let records = vec![
+            record(0x100, 0x200, BranchKind::DirectJump, Outcome::T),
+        ];
+        let cfg = ControlFlowGraph::build(&records);
+        let block = &cfg.blocks[&0x100];
+        assert_eq!(block.start, 0x100);
+        assert_eq!(block.end, 0x100);
+        assert!(matches!(block.terminator, Terminator::Direct(CfgEdge { target: 0x200, count: 1 })));
+    }
+
+    #[test]
+    fn conditional_branch_splits_a_block_taken_and_fallthrough() {
+        let records = vec![
+            record(0x100, 0x200, BranchKind::DirectBranch, Outcome::T),
+            record(0x200, 0x300, BranchKind::DirectJump, Outcome::T),
+            // Revisit the same branch, not-taken this time, to land on its
+            // own fallthrough (0x104) as a second observed successor.
+            record(0x100, 0x200, BranchKind::DirectBranch, Outcome::N),
+        ];
+        let cfg = ControlFlowGraph::build(&records);
+        let block = &cfg.blocks[&0x100];
+        match &block.terminator {
+            Terminator::Conditional { taken, not_taken } => {
+                assert_eq!(taken.target, 0x200);
+                assert_eq!(taken.count, 1);
+                assert_eq!(not_taken.target, 0x104);
+                assert_eq!(not_taken.count, 1);
+            },
+            other => panic!("expected Conditional, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_leader_discovered_mid_block_splits_it() {
+        // A delay-slot jump at 0x100 doesn't register its own fallthrough
+        // as a leader, so a second branch at 0x108 (with no known leader
+        // in between) extends the same block, [0x100, 0x108], instead of
+        // starting a new one. That second branch's own target (0x104)
+        // falls strictly inside the block it just grew, so registering it
+        // as a leader must immediately split that block into a
+        // fallthrough prefix and a suffix that inherits the terminator.
+        let records = vec![
+            record_delay_slot(0x100, 0x900, BranchKind::DirectJump, Outcome::T),
+            record(0x108, 0x104, BranchKind::DirectJump, Outcome::T),
+        ];
+        let cfg = ControlFlowGraph::build(&records);
+
+        let prefix = &cfg.blocks[&0x100];
+        assert_eq!(prefix.start, 0x100);
+        assert!(matches!(prefix.terminator, Terminator::Fallthrough(0x104)));
+
+        let suffix = &cfg.blocks[&0x104];
+        assert_eq!(suffix.end, 0x108);
+        assert!(matches!(suffix.terminator, Terminator::Direct(CfgEdge { target: 0x900, count: 2 })));
+    }
+
+    #[test]
+    fn indirect_branch_accumulates_per_target_counts() {
+        let records = vec![
+            record(0x100, 0x400, BranchKind::IndirectJump, Outcome::T),
+            record(0x100, 0x500, BranchKind::IndirectJump, Outcome::T),
+            record(0x100, 0x400, BranchKind::IndirectJump, Outcome::T),
+        ];
+        let cfg = ControlFlowGraph::build(&records);
+        let block = &cfg.blocks[&0x100];
+        match &block.terminator {
+            Terminator::Indirect(targets) => {
+                assert_eq!(targets[&0x400], 2);
+                assert_eq!(targets[&0x500], 1);
+            },
+            other => panic!("expected Indirect, got {:?}", other),
+        }
+    }
+}
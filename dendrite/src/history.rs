@@ -3,6 +3,7 @@ use bitvec::prelude::*;
 use std::ops::{ RangeInclusive };
 
 
+#[derive(Clone)]
 pub struct HistoryRegister {
     pub data: BitVec<usize, Lsb0>,
     len: usize,
@@ -78,8 +79,10 @@ impl HistoryRegister {
 /// in "BADGR: A Practical GHR Implementation for TAGE Branch Predictors"
 /// (Schlais and Lipasti, 2016).
 ///
-/// NOTE: I think this is only relevant if you're shifting in a single bit. 
-/// You'd have to rewrite this if you want to use some other strategy. 
+/// Use [`Self::update`] when exactly one history bit is shifted in between
+/// calls, and [`Self::update_by`] when more than one bit needs to be folded
+/// in at once (e.g. replaying a trace where several outcomes land between
+/// predictions).
 ///
 #[derive(Clone, Debug)]
 pub struct FoldedHistoryRegister {
@@ -130,6 +133,121 @@ impl FoldedHistoryRegister {
         // The last relevant history bit will be XOR'ed with this bit
         self.data.set(index, last_bit);
     }
+
+    /// Like [`Self::update`], but folds in `n` freshly-shifted history bits
+    /// at once instead of requiring one [`Self::update`] call per bit.
+    ///
+    /// This replays the single-bit recurrence `n` times, but without ever
+    /// reading `ghr` mid-replay: the j-th replayed step needs whatever
+    /// `ghr` held at `ghist_range`'s two ends just *before* the remaining
+    /// `n - 1 - j` bits were shifted in, which is exactly what `ghr` holds
+    /// *now* at those same positions shifted forward by `n - 1 - j` - so
+    /// every step's newest/oldest bit can be read straight out of the
+    /// final `ghr` passed in here.
+    ///
+    /// Falls back to a full fold over `ghist_range` when `n` is large enough
+    /// that the rotation would collapse (`n >= output_size`) or the whole
+    /// window is replaced in one shot (`n >= ghist_range` length).
+    pub fn update_by(&mut self, ghr: &HistoryRegister, n: usize) {
+        if n == 0 { return; }
+
+        let ghist_size = self.ghist_range.end() - self.ghist_range.start();
+        let window_len = ghist_size + 1;
+
+        if n >= self.output_size || n >= window_len {
+            let folded = ghr.fold(self.ghist_range.clone(), self.output_size);
+            self.data = bitvec![0; self.output_size];
+            self.data.store::<usize>(folded);
+            return;
+        }
+
+        let lo = *self.ghist_range.start();
+        let hi = *self.ghist_range.end();
+        let index = ghist_size % self.output_size;
+
+        for j in 0..n {
+            let newest_bit = ghr.data()[lo + n - 1 - j];
+            let oldest_bit = ghr.data()[hi + n - 1 - j];
+            let first_bit  = newest_bit ^ self.data[0];
+            let last_bit   = oldest_bit ^ self.data[index];
+
+            self.data.rotate_right(1);
+            self.data.set(0, first_bit);
+            self.data.set(index, last_bit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn shift_in(ghr: &mut HistoryRegister, bit: bool) {
+        ghr.shift_by(1);
+        ghr.data_mut().set(0, bit);
+    }
+
+    #[test]
+    fn update_by_matches_n_sequential_single_bit_updates() {
+        let bits = [true, false, true, true, false];
+
+        let mut ghr_seq = HistoryRegister::new(32);
+        let mut fh_seq = FoldedHistoryRegister::new(8, 0..=15);
+        for &b in bits.iter() {
+            shift_in(&mut ghr_seq, b);
+            fh_seq.update(&ghr_seq);
+        }
+
+        let mut ghr_once = HistoryRegister::new(32);
+        for &b in bits.iter() {
+            shift_in(&mut ghr_once, b);
+        }
+        let mut fh_once = FoldedHistoryRegister::new(8, 0..=15);
+        fh_once.update_by(&ghr_once, bits.len());
+
+        assert_eq!(fh_seq.output_usize(), fh_once.output_usize());
+    }
+
+    #[test]
+    fn update_by_matches_sequential_updates_from_nonzero_prior_state() {
+        let prior = [true, false, false, true, true, false, true];
+        let bits = [false, true, true, false, false];
+
+        let mut ghr_seq = HistoryRegister::new(32);
+        let mut fh_seq = FoldedHistoryRegister::new(6, 0..=20);
+        for &b in prior.iter().chain(bits.iter()) {
+            shift_in(&mut ghr_seq, b);
+            fh_seq.update(&ghr_seq);
+        }
+
+        let mut ghr_once = HistoryRegister::new(32);
+        let mut fh_once = FoldedHistoryRegister::new(6, 0..=20);
+        for &b in prior.iter() {
+            shift_in(&mut ghr_once, b);
+            fh_once.update(&ghr_once);
+        }
+        for &b in bits.iter() {
+            shift_in(&mut ghr_once, b);
+        }
+        fh_once.update_by(&ghr_once, bits.len());
+
+        assert_eq!(fh_seq.output_usize(), fh_once.output_usize());
+    }
+
+    #[test]
+    fn update_by_falls_back_to_a_full_fold_when_n_exceeds_the_window() {
+        let bits = [true, false, true, true, false, true, true, false, true, false];
+        let mut ghr = HistoryRegister::new(32);
+        for &b in bits.iter() {
+            shift_in(&mut ghr, b);
+        }
+
+        let mut fh = FoldedHistoryRegister::new(4, 0..=7);
+        fh.update_by(&ghr, bits.len());
+
+        let expected = ghr.fold(0..=7, 4);
+        assert_eq!(fh.output_usize(), expected);
+    }
 }
 
 
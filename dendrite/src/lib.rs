@@ -9,11 +9,13 @@ pub mod predictor;
 pub mod trace;
 pub mod branch;
 pub mod analysis;
+pub mod cfg;
 
 pub use branch::*;
 pub use trace::*;
 pub use history::*;
 pub use predictor::*;
 pub use analysis::*;
+pub use cfg::*;
 
 
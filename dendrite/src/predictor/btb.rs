@@ -60,3 +60,306 @@ impl SimpleBTB {
 //        &mut self.data[idx]
 //    }
 //}
+
+/// A strategy for choosing which way to evict within a set once every way
+/// is occupied.
+pub trait ReplacementPolicy {
+    /// Build policy state for a table with `num_sets` sets of `ways` ways
+    /// each.
+    fn new(num_sets: usize, ways: usize) -> Self where Self: Sized;
+
+    /// Record an access to `way` within `set` (on a hit or a fill),
+    /// updating whatever state the policy tracks for future victim
+    /// selection.
+    fn on_access(&mut self, set: usize, way: usize);
+
+    /// Choose a way to evict within `set`.
+    fn pick_victim(&self, set: usize) -> usize;
+}
+
+/// Evicts the least-recently-used way in each set, tracked as an
+/// explicit recency order.
+pub struct LruPolicy {
+    /// `order[set]` lists ways from least- to most-recently-used.
+    order: Vec<Vec<usize>>,
+}
+impl ReplacementPolicy for LruPolicy {
+    fn new(num_sets: usize, ways: usize) -> Self {
+        Self { order: vec![(0..ways).collect(); num_sets] }
+    }
+
+    fn on_access(&mut self, set: usize, way: usize) {
+        let order = &mut self.order[set];
+        if let Some(pos) = order.iter().position(|&w| w == way) {
+            order.remove(pos);
+        }
+        order.push(way);
+    }
+
+    fn pick_victim(&self, set: usize) -> usize {
+        self.order[set][0]
+    }
+}
+
+/// Evicts via a tree of pseudo-LRU bits per set: each internal node of a
+/// binary tree over the `ways` leaves points toward its less-recently-used
+/// child, and an access flips every bit along the path to the accessed
+/// leaf to point away from it. Requires `ways` to be a power of two.
+pub struct TreePlruPolicy {
+    ways: usize,
+    /// `bits[set]` has `ways - 1` entries; `bits[set][i]` is the tree node
+    /// at index `i` in level order (`false` = favor the left child as the
+    /// next victim, `true` = favor the right child).
+    bits: Vec<Vec<bool>>,
+}
+impl ReplacementPolicy for TreePlruPolicy {
+    fn new(num_sets: usize, ways: usize) -> Self {
+        assert!(ways.is_power_of_two());
+        Self { ways, bits: vec![vec![false; ways - 1]; num_sets] }
+    }
+
+    fn on_access(&mut self, set: usize, way: usize) {
+        let bits = &mut self.bits[set];
+        let mut node = 0;
+        let mut lo = 0;
+        let mut hi = self.ways;
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if way < mid {
+                bits[node] = true;
+                node = 2 * node + 1;
+                hi = mid;
+            } else {
+                bits[node] = false;
+                node = 2 * node + 2;
+                lo = mid;
+            }
+        }
+    }
+
+    fn pick_victim(&self, set: usize) -> usize {
+        let bits = &self.bits[set];
+        let mut node = 0;
+        let mut lo = 0;
+        let mut hi = self.ways;
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            // Follow the bit toward the side it favors as a victim (see
+            // the field doc above), the mirror image of on_access's walk.
+            if bits[node] {
+                node = 2 * node + 2;
+                lo = mid;
+            } else {
+                node = 2 * node + 1;
+                hi = mid;
+            }
+        }
+        lo
+    }
+}
+
+/// Evicts a uniformly-random way in each set. Tracks no per-set state.
+pub struct RandomPolicy {
+    ways: usize,
+}
+impl ReplacementPolicy for RandomPolicy {
+    fn new(_num_sets: usize, ways: usize) -> Self {
+        Self { ways }
+    }
+
+    fn on_access(&mut self, _set: usize, _way: usize) {}
+
+    fn pick_victim(&self, _set: usize) -> usize {
+        rand::random::<usize>() % self.ways
+    }
+}
+
+/// A set-associative branch target buffer: each program counter maps to a
+/// set (via its low bits), and within that set, up to `ways` entries are
+/// searched by tag - modeling the conflict/aliasing behavior that a
+/// direct-mapped [`SimpleBTB`] cannot.
+pub struct SetAssociativeBTB<P: ReplacementPolicy> {
+    num_sets: usize,
+    ways: usize,
+    /// `data[set][way]` holds the tag and payload currently occupying
+    /// that slot.
+    data: Vec<Vec<Option<(usize, SimpleBTBEntry)>>>,
+    policy: P,
+
+    hits: usize,
+    misses: usize,
+    set_hits: Vec<usize>,
+    set_misses: Vec<usize>,
+}
+impl<P: ReplacementPolicy> SetAssociativeBTB<P> {
+    pub fn new(num_sets: usize, ways: usize) -> Self {
+        assert!(num_sets.is_power_of_two());
+        Self {
+            num_sets,
+            ways,
+            data: vec![vec![None; ways]; num_sets],
+            policy: P::new(num_sets, ways),
+            hits: 0,
+            misses: 0,
+            set_hits: vec![0; num_sets],
+            set_misses: vec![0; num_sets],
+        }
+    }
+
+    fn set_of(&self, pc: usize) -> usize { pc & (self.num_sets - 1) }
+    fn tag_of(&self, pc: usize) -> usize { pc / self.num_sets }
+
+    fn find_way(&self, set: usize, tag: usize) -> Option<usize> {
+        self.data[set].iter().position(|slot| matches!(slot, Some((t, _)) if *t == tag))
+    }
+
+    /// Look up `pc`, recording a hit/miss (aggregate and per-set) and a
+    /// replacement-policy access on a hit.
+    pub fn lookup(&mut self, pc: usize) -> Option<SimpleBTBEntry> {
+        let set = self.set_of(pc);
+        let tag = self.tag_of(pc);
+        match self.find_way(set, tag) {
+            Some(way) => {
+                self.policy.on_access(set, way);
+                self.hits += 1;
+                self.set_hits[set] += 1;
+                self.data[set][way].map(|(_, entry)| entry)
+            },
+            None => {
+                self.misses += 1;
+                self.set_misses[set] += 1;
+                None
+            },
+        }
+    }
+
+    /// Insert or update the entry for `pc`, filling an empty way if one
+    /// is available in the set, or asking the replacement policy to pick
+    /// a victim otherwise.
+    pub fn insert(&mut self, pc: usize, entry: SimpleBTBEntry) {
+        let set = self.set_of(pc);
+        let tag = self.tag_of(pc);
+        let way = self.find_way(set, tag)
+            .or_else(|| self.data[set].iter().position(Option::is_none))
+            .unwrap_or_else(|| self.policy.pick_victim(set));
+        self.data[set][way] = Some((tag, entry));
+        self.policy.on_access(set, way);
+    }
+
+    pub fn hits(&self) -> usize { self.hits }
+    pub fn misses(&self) -> usize { self.misses }
+    pub fn set_hits(&self, set: usize) -> usize { self.set_hits[set] }
+    pub fn set_misses(&self, set: usize) -> usize { self.set_misses[set] }
+}
+
+impl<P: ReplacementPolicy> PredictorTable for SetAssociativeBTB<P> {
+    /// The type of input to the table used to form an index.
+    type Input<'a> = usize;
+
+    /// A resolved `(set, way)` slot.
+    type Index = (usize, usize);
+
+    /// The type of entry in the table.
+    type Entry = SimpleBTBEntry;
+
+    /// Returns the number of entries in the table.
+    fn size(&self) -> usize { self.num_sets * self.ways }
+
+    /// Resolve `pc` to the `(set, way)` slot that currently holds a
+    /// matching tag, or the policy's current victim way on a miss.
+    ///
+    /// Unlike [`lookup`](Self::lookup) and [`insert`](Self::insert), this
+    /// is a read-only peek: [`PredictorTable::get_index`] only takes
+    /// `&self`, so it cannot record hit/miss counters or update
+    /// replacement-policy state the way a real access would.
+    fn get_index(&self, input: Self::Input<'_>) -> Self::Index {
+        let set = self.set_of(input);
+        let tag = self.tag_of(input);
+        let way = self.find_way(set, tag).unwrap_or_else(|| self.policy.pick_victim(set));
+        (set, way)
+    }
+
+    /// Returns a reference to an entry in the table.
+    fn get_entry(&self, idx: Self::Index) -> &Self::Entry {
+        self.data[idx.0][idx.1].as_ref().map(|(_, entry)| entry)
+            .expect("no entry at this index")
+    }
+
+    /// Returns a mutable reference to an entry in the table.
+    fn get_entry_mut(&mut self, idx: Self::Index) -> &mut Self::Entry {
+        self.data[idx.0][idx.1].as_mut().map(|(_, entry)| entry)
+            .expect("no entry at this index")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(tgt: usize) -> SimpleBTBEntry {
+        SimpleBTBEntry::new(tgt, BranchKind::DirectJump)
+    }
+
+    #[test]
+    fn lru_policy_evicts_the_least_recently_used_way() {
+        let mut policy = LruPolicy::new(1, 2);
+        // Freshly built: way 0 is the least-recently-used.
+        assert_eq!(policy.pick_victim(0), 0);
+
+        // Touching way 0 makes way 1 the new victim.
+        policy.on_access(0, 0);
+        assert_eq!(policy.pick_victim(0), 1);
+    }
+
+    #[test]
+    fn tree_plru_policy_steers_the_victim_away_from_recent_accesses() {
+        let mut policy = TreePlruPolicy::new(1, 4);
+        // All bits start false, landing on leaf 0.
+        assert_eq!(policy.pick_victim(0), 0);
+
+        // Touching leaf 0 steers the next victim into the other half of
+        // the tree, away from the just-accessed leaf.
+        policy.on_access(0, 0);
+        assert_eq!(policy.pick_victim(0), 2);
+
+        policy.on_access(0, 3);
+        assert_eq!(policy.pick_victim(0), 1);
+
+        policy.on_access(0, 1);
+        assert_eq!(policy.pick_victim(0), 2);
+    }
+
+    #[test]
+    fn set_associative_btb_hits_on_matching_tag_and_misses_otherwise() {
+        let mut btb: SetAssociativeBTB<LruPolicy> = SetAssociativeBTB::new(1, 2);
+        btb.insert(0x10, entry(0x1000));
+
+        assert_eq!(btb.lookup(0x10), Some(entry(0x1000)));
+        assert_eq!(btb.hits(), 1);
+        assert_eq!(btb.misses(), 0);
+
+        assert_eq!(btb.lookup(0x20), None);
+        assert_eq!(btb.hits(), 1);
+        assert_eq!(btb.misses(), 1);
+    }
+
+    #[test]
+    fn set_associative_btb_evicts_lru_way_once_the_set_is_full() {
+        // 1 set, 2 ways: tag_of(pc) == pc since num_sets == 1, so 0x10 and
+        // 0x20 fill the two ways of the only set.
+        let mut btb: SetAssociativeBTB<LruPolicy> = SetAssociativeBTB::new(1, 2);
+        btb.insert(0x10, entry(0x100));
+        btb.insert(0x20, entry(0x200));
+
+        // Touch 0x10 so 0x20's way becomes the least-recently-used one.
+        btb.lookup(0x10);
+
+        // A third distinct tag forces an eviction; it should take 0x20's
+        // way, leaving 0x10 resident.
+        btb.insert(0x30, entry(0x300));
+
+        assert_eq!(btb.lookup(0x10), Some(entry(0x100)));
+        assert_eq!(btb.lookup(0x30), Some(entry(0x300)));
+        assert_eq!(btb.lookup(0x20), None);
+    }
+}
@@ -61,23 +61,98 @@ impl SaturatingCounter {
     pub fn set_direction(&mut self, outcome: Outcome) {
         self.state = outcome;
     }
+
+    /// Return the counter's internal confidence in the current direction,
+    /// where `0` is the weakest (having just crossed over from the
+    /// opposite direction, or just been allocated).
+    pub fn magnitude(&self) -> u8 { self.ctr }
 }
 
-impl StatefulPredictor for SaturatingCounter { 
+impl StatefulPredictor for SaturatingCounter {
     fn name(&self) -> &'static str { "SaturatingCounter" }
     fn predict(&self) -> Outcome { self.state }
-    fn reset(&mut self) { 
-        self.state = self.cfg.default_state; 
+    fn reset(&mut self) {
+        self.state = self.cfg.default_state;
         self.ctr = 0;
     }
     fn update(&mut self, outcome: Outcome) {
         let prediction = self.predict();
         if outcome != prediction {
             self.weaken();
-        } else { 
+        } else {
             self.strengthen();
         }
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn counter() -> SaturatingCounter {
+        SaturatingCounterConfig {
+            max_t_state: 3,
+            max_n_state: 3,
+            default_state: Outcome::N,
+        }.build()
+    }
+
+    #[test]
+    fn repeated_agreement_strengthens_up_to_the_configured_limit() {
+        let mut c = counter();
+        assert_eq!(c.magnitude(), 0);
+        for expected in 1..=3 {
+            c.update(Outcome::N);
+            assert_eq!(c.magnitude(), expected);
+        }
+        // Already at the limit - another agreeing update can't go further.
+        c.update(Outcome::N);
+        assert_eq!(c.magnitude(), 3);
+        assert_eq!(c.predict(), Outcome::N);
+    }
+
+    #[test]
+    fn repeated_disagreement_flips_state_once_magnitude_bottoms_out() {
+        let mut c = counter();
+        c.update(Outcome::N);
+        c.update(Outcome::N);
+        assert_eq!(c.magnitude(), 2);
+
+        // Disagreeing updates weaken the counter back toward zero...
+        c.update(Outcome::T);
+        assert_eq!(c.magnitude(), 1);
+        assert_eq!(c.predict(), Outcome::N);
+
+        c.update(Outcome::T);
+        assert_eq!(c.magnitude(), 0);
+        assert_eq!(c.predict(), Outcome::N);
+
+        // ...and one more flips the predicted direction, resetting
+        // magnitude to its weakest value.
+        c.update(Outcome::T);
+        assert_eq!(c.magnitude(), 0);
+        assert_eq!(c.predict(), Outcome::T);
+    }
+
+    #[test]
+    fn reset_restores_the_default_state_and_zero_magnitude() {
+        let mut c = counter();
+        c.update(Outcome::N);
+        c.update(Outcome::N);
+        c.set_direction(Outcome::T);
+        assert_eq!(c.predict(), Outcome::T);
+
+        c.reset();
+        assert_eq!(c.predict(), Outcome::N);
+        assert_eq!(c.magnitude(), 0);
+    }
+
+    #[test]
+    fn set_strength_clamps_to_the_limit_for_the_current_direction() {
+        let mut c = counter();
+        c.set_strength(100);
+        assert_eq!(c.magnitude(), 3);
+    }
+}
+
 
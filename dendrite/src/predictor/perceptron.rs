@@ -0,0 +1,188 @@
+//! Implementation of perceptron-based predictors.
+
+use std::collections::VecDeque;
+use crate::Outcome;
+use crate::predictor::PredictorConfiguration;
+
+/// Perceptron [with integer weights].
+///
+/// See the following papers:
+///
+/// - "Neural Methods for Dynamic Branch Prediction" (Jiménez and Lin, 2002)
+/// - "Fast Path-Based Neural Branch Prediction" (Jiménez, 2003)
+///
+pub struct Perceptron<const L: usize> {
+    pub weights: [i8; L],
+    pub bias: i8,
+}
+impl <const L: usize> Perceptron<L> {
+
+    // Training threshold.
+    // Papers suggest this constant (based on the history size).
+    pub const THETA: i8 = ((1.93f32 * (L as f32)) + 14.0f32) as i8;
+
+    pub fn new() -> Self {
+        Self { weights: [0; L], bias: 0, }
+    }
+
+    /// Reset the state.
+    pub fn reset(&mut self) {
+        self.bias = 0;
+        self.weights = [0; L];
+    }
+
+    /// Return a reference to the list of weights.
+    pub fn weights(&self) -> &[i8] {
+        &self.weights
+    }
+
+    /// Compute the dot product of the input/weight vectors
+    fn dot_product(&self, input: &[i8]) -> i8 {
+        assert!(input.len() == L);
+        input.iter().zip(self.weights.iter())
+            .map(|(i, w)| i.saturating_mul(*w))
+            .fold(0, |mut sum, val| { sum = sum.saturating_add(val); sum })
+    }
+
+    /// Convert from an [Outcome] into an [i8].
+    fn outcome_to_val(outcome: Outcome) -> i8 {
+        match outcome {
+            Outcome::T => 1,
+            Outcome::N => -1,
+        }
+    }
+
+    /// Given some input vector, compute the output value.
+    /// The predicted outcome is determined by the sign of the output.
+    pub fn output(&self, input: &[i8]) -> (i8, Outcome) {
+        let res = self.dot_product(input).saturating_add(self.bias);
+        let out = if res >= 0 { Outcome::T } else { Outcome::N };
+        (res, out)
+    }
+
+    /// Given some outcome, adjust the weights.
+    pub fn train(&mut self, input: &[i8], outcome: Outcome) {
+        let (output, prediction) = self.output(&input);
+        let outcome_val: i8 = Self::outcome_to_val(outcome);
+
+        // Training occurs after a misprediction, or when the output value is
+        // below some threshold [Perceptron::THETA].
+        let miss = (prediction != outcome);
+        let output_magnitude = {
+            if output > i8::MIN { output.abs() } else { (output + 1).abs() }
+        };
+        let below_threshold  = (output_magnitude <= Self::THETA);
+
+        // When a bit in the history matches the outcome, increment the
+        // corresponding weight. Otherwise, decrement the corresponding weight.
+        if miss || below_threshold {
+            self.bias = self.bias.saturating_add(outcome_val);
+            for idx in 0..L {
+                let adj = if input[idx] == outcome_val { 1 } else { -1 };
+                self.weights[idx] = self.weights[idx].saturating_add(adj);
+            }
+        }
+    }
+}
+
+/// Configuration for building a [`PathPerceptronPredictor`].
+#[derive(Clone, Copy, Debug)]
+pub struct PathPerceptronConfig<const L: usize> {
+    /// Number of rows in the perceptron table (must be a power of two).
+    pub rows: usize,
+}
+impl <const L: usize> PathPerceptronConfig<L> {
+    pub fn new(rows: usize) -> Self {
+        assert!(rows.is_power_of_two());
+        Self { rows }
+    }
+
+    /// The training threshold used by every row in the table.
+    pub fn theta(&self) -> i8 { Perceptron::<L>::THETA }
+}
+impl <const L: usize> PredictorConfiguration for PathPerceptronConfig<L> {
+    type Predictor = PathPerceptronPredictor<L>;
+
+    fn storage_bits(&self) -> usize {
+        self.rows * (L + 1) * 8
+    }
+
+    fn build(self) -> Self::Predictor {
+        let rows = (0..self.rows).map(|_| Perceptron::<L>::new()).collect();
+        PathPerceptronPredictor {
+            cfg: self,
+            rows,
+            ghr: [-1; L],
+            path: VecDeque::with_capacity(L),
+        }
+    }
+}
+
+/// A path-based hashed-perceptron predictor.
+///
+/// Unlike a plain [`Perceptron`] indexed only by the current branch's
+/// program counter, this keeps a table of perceptrons indexed by PC and,
+/// at predict time, composes an output from weights taken from the
+/// perceptrons belonging to the last `L` branches along the speculative
+/// path: weight `i` comes from the row of the branch encountered `i + 1`
+/// branches ago. This follows the "path-based" scheme from "Fast
+/// Path-Based Neural Branch Prediction" (Jiménez, 2003), rather than the
+/// simpler scheme where a single row's own weights are indexed by global
+/// history bits.
+///
+/// Each row is still trained with the ordinary [`Perceptron::train`]
+/// against the running global-history bit vector, so a row's weights
+/// remain meaningful both when read directly (for its own prediction)
+/// and when read along someone else's path.
+pub struct PathPerceptronPredictor<const L: usize> {
+    cfg: PathPerceptronConfig<L>,
+    rows: Vec<Perceptron<L>>,
+
+    /// Global history of the last `L` outcomes, encoded as `{-1, 1}`, used
+    /// as the input vector for [`Perceptron::train`]/[`Perceptron::output`].
+    ghr: [i8; L],
+
+    /// Program counters of the last (up to) `L` branches, most-recent-first.
+    path: VecDeque<usize>,
+}
+impl <const L: usize> PathPerceptronPredictor<L> {
+    fn index(&self, pc: usize) -> usize {
+        pc & (self.cfg.rows - 1)
+    }
+
+    /// Predict the outcome of the branch at `pc`, composing the output
+    /// from `pc`'s own bias and a weight contributed by each branch still
+    /// live in the speculative path.
+    pub fn predict(&self, pc: usize) -> Outcome {
+        let row = &self.rows[self.index(pc)];
+        let mut sum: i32 = row.bias as i32;
+        for (i, &old_pc) in self.path.iter().enumerate() {
+            let other = &self.rows[self.index(old_pc)];
+            sum += other.weights()[i] as i32;
+        }
+        if sum >= 0 { Outcome::T } else { Outcome::N }
+    }
+
+    /// Train `pc`'s row with the real outcome, then push `pc`/the outcome
+    /// onto the speculative path/history for subsequent predictions.
+    pub fn update(&mut self, pc: usize, outcome: Outcome) {
+        let idx = self.index(pc);
+        self.rows[idx].train(&self.ghr, outcome);
+
+        self.ghr.rotate_right(1);
+        self.ghr[0] = if outcome == Outcome::T { 1 } else { -1 };
+
+        self.path.push_front(pc);
+        self.path.truncate(L);
+    }
+
+    pub fn reset(&mut self) {
+        for row in self.rows.iter_mut() {
+            row.reset();
+        }
+        self.ghr = [-1; L];
+        self.path.clear();
+    }
+
+    pub fn name(&self) -> &'static str { "PathPerceptronPredictor" }
+}
@@ -3,18 +3,54 @@
 pub mod component;
 pub mod stat;
 pub mod config;
+pub mod statistical_corrector;
+pub mod loop_predictor;
 
 pub use component::*;
 pub use stat::*;
 pub use config::*;
+pub use statistical_corrector::*;
+pub use loop_predictor::*;
+
+use std::collections::VecDeque;
 
 use bitvec::prelude::*;
+use rand::Rng;
 use rand::distributions::{ WeightedIndex, Distribution };
 
 use crate::history::*;
 use crate::Outcome;
 use crate::predictor::*;
 
+/// Number of `USE_ALT_ON_NA` counters (see [`TAGEPredictor::predict`]),
+/// indexed by a hash of the winning provider's index/tag.
+pub(crate) const USE_ALT_ON_NA_SIZE: usize = 16;
+
+/// Bounds for the signed saturating `USE_ALT_ON_NA` counters.
+const USE_ALT_ON_NA_MAX: i8 = 7;
+const USE_ALT_ON_NA_MIN: i8 = -8;
+
+/// Bounded capacity of [`TAGEPredictor::history_log`]. Speculative
+/// insertions older than this many [`TAGEPredictor::update_history`]
+/// calls can no longer be [`TAGEPredictor::restore`]d.
+const HISTORY_LOG_CAPACITY: usize = 1024;
+
+/// One entry in [`TAGEPredictor::history_log`]: the state of the global
+/// history register, every tagged component's folded-history CSR, and (if
+/// configured) the [`StatisticalCorrector`]'s own GEHL CSRs, immediately
+/// *before* a single speculative history bit was inserted, tagged with the
+/// sequence number assigned to that insertion.
+#[derive(Clone)]
+struct HistoryCheckpoint {
+    seq: u64,
+    ghr: HistoryRegister,
+    csr: Vec<FoldedHistoryRegister>,
+
+    /// Snapshot of the [`StatisticalCorrector`]'s own GEHL CSRs, if a
+    /// statistical corrector is configured - see [`StatisticalCorrector::csr_snapshot`].
+    sc_csr: Option<Vec<FoldedHistoryRegister>>,
+}
+
 /// Container for inputs passed to a [`TAGEPredictor`] and its components.
 #[derive(Clone)]
 pub struct TAGEInputs {
@@ -28,12 +64,44 @@ pub struct TAGEInputs {
 
 /// Identifies a particular component in a [`TAGEPredictor`].
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum TAGEProvider { 
+pub enum TAGEProvider {
     /// The base component
-    Base, 
+    Base,
 
     /// A tagged component
-    Tagged(usize), 
+    Tagged(usize),
+}
+
+/// Identifies which stage ultimately provided a [`TAGEPredictor`]'s
+/// predicted direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TAGEStage {
+    /// The TAGE provider/alt-provider machinery (possibly after a
+    /// `USE_ALT_ON_NA` override - see [`TAGEPredictor::predict`]).
+    Tage,
+
+    /// The [`StatisticalCorrector`] overrode the TAGE stage.
+    Corrector,
+
+    /// A confident [`LoopPredictor`] entry overrode everything else.
+    Loop,
+}
+
+/// Which half of each two-bit 'useful' counter the next periodic aging
+/// event should clear - see [`TAGEPredictor::update`].
+///
+/// Clearing a full 'useful' bit field at once (as in the original TAGE
+/// paper) discards every entry's learned usefulness in one shot. Seznec's
+/// graceful-aging scheme (used in gem5's `tage_base`) instead alternates
+/// between clearing only the high bit and only the low bit on successive
+/// reset events, so an entry that's still useful in the other bit
+/// survives a single reset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UsefulBitPhase {
+    /// Clear the high bit of every 'useful' counter on the next reset.
+    High,
+    /// Clear the low bit of every 'useful' counter on the next reset.
+    Low,
 }
 
 /// Container for output from [`TAGEPredictor::predict`], including the 
@@ -63,6 +131,20 @@ pub struct TAGEPrediction {
 
     /// The tag matching the entry from the alternate component
     pub alt_tag: usize,
+
+    /// The raw predicted direction from the winning provider's entry,
+    /// before any `USE_ALT_ON_NA` override is applied to `outcome`.
+    pub raw_outcome: Outcome,
+
+    /// Whether the winning provider's entry was "pseudo-newly-allocated"
+    /// (its counter was at the weakest magnitude) at prediction time.
+    pub pseudo_new: bool,
+
+    /// Which stage ultimately provided `outcome`.
+    pub stage: TAGEStage,
+
+    /// Whether the [`StatisticalCorrector`] overrode the TAGE stage.
+    pub sc_override: bool,
 }
 
 
@@ -83,12 +165,43 @@ pub struct TAGEPredictor {
     /// Tagged components
     pub comp: Vec<TAGEComponent>,
 
-    /// Counter used to periodically reset all 'useful' counters
+    /// Counter used to periodically age all 'useful' counters
     pub reset_ctr: u8,
+
+    /// Which half of each 'useful' counter the next aging event clears -
+    /// see [`UsefulBitPhase`].
+    pub reset_phase: UsefulBitPhase,
+
+    /// Signed saturating counters used to decide, for a given
+    /// newly-allocated entry, whether the alternate prediction should be
+    /// trusted instead (the `USE_ALT_ON_NA` mechanism from the gem5 TAGE
+    /// base) - see [`TAGEPredictor::predict`].
+    pub use_alt_on_na: [i8; USE_ALT_ON_NA_SIZE],
+
+    /// Optional post-TAGE correction stage - see [`TAGEPredictor::predict`].
+    pub sc: Option<StatisticalCorrector>,
+
+    /// Optional loop predictor, given the final say over everything else
+    /// when confident - see [`TAGEPredictor::predict`].
+    pub loop_pred: Option<LoopPredictor>,
+
+    /// Sequence number assigned to the next [`TAGEPredictor::update_history`]
+    /// call.
+    seq: u64,
+
+    /// Ring buffer of recent [`HistoryCheckpoint`]s, used by
+    /// [`TAGEPredictor::restore`] to recover folded-history state after a
+    /// speculatively-predicted branch is discovered to be mispredicted.
+    history_log: VecDeque<HistoryCheckpoint>,
 }
 impl TAGEPredictor {
 
-    /// Access the base component using the provided input. 
+    /// Hash a winning provider's index/tag into a `USE_ALT_ON_NA` slot.
+    fn use_alt_on_na_index(idx: usize, tag: usize) -> usize {
+        (idx ^ tag) % USE_ALT_ON_NA_SIZE
+    }
+
+    /// Access the base component using the provided input.
     fn get_base_entry(&self, input: TAGEInputs) 
         -> (usize, &SaturatingCounter)
     {
@@ -110,32 +223,34 @@ impl TAGEPredictor {
         entries
     }
 
-    /// Given a program counter value and the provider of an incorrect 
-    /// prediction, try to select a tagged component that will be used to 
-    /// allocate a new entry. 
+    /// Given a program counter value and the provider of an incorrect
+    /// prediction, find every tagged component with a longer history than
+    /// the provider whose entry for this input is free (`useful == 0`),
+    /// ordered from the closest (shortest-history) eligible component to
+    /// the furthest (longest-history) one.
     ///
-    /// Returns [None] if we fail to allocate a new entry. 
-    fn alloc(&self, input: TAGEInputs, provider: TAGEProvider) 
-        -> Option<usize>
-    { 
-        // Early return: when the provider is the component with the longest 
+    /// Returns an empty [Vec] if no component is eligible.
+    fn alloc_candidates(&self, input: TAGEInputs, provider: TAGEProvider)
+        -> Vec<usize>
+    {
+        // Early return: when the provider is the component with the longest
         // associated history length, we cannot allocate.
         //
-        // NOTE: Remember that the provider with the longest history can 
+        // NOTE: Remember that the provider with the longest history can
         // always be found at index 0.
         if matches!(provider, TAGEProvider::Tagged(0)) {
-            return None;
+            return Vec::new();
         }
 
         // Get the indexes of all components whose associated history length
-        // is longer than the provider. 
-        let provider_range = match provider { 
+        // is longer than the provider.
+        let provider_range = match provider {
             TAGEProvider::Base => 0..=self.shortest_tagged_component(),
             TAGEProvider::Tagged(idx) => 0..=(idx-1),
         };
 
         // A component is only eligible when the entry associated with this
-        // program counter has its 'useful' bits set to zero. 
+        // program counter has its 'useful' bits set to zero.
         let mut candidates: Vec<usize> = Vec::new();
         for idx in provider_range {
             let index = self.comp[idx].get_index(input.clone());
@@ -145,28 +260,39 @@ impl TAGEPredictor {
             }
         }
 
-        // Easy case: we failed allocate a new entry
-        if candidates.is_empty() {
-            return None;
-        }
+        // `provider_range` runs from the longest-history component (index
+        // 0) down to the one just shorter than the provider, so reverse it
+        // to put the closest (shortest-history) eligible component first -
+        // the reference design always allocates that one before
+        // probabilistically reaching for a longer one.
+        candidates.reverse();
+        candidates
+    }
 
-        // Easy case: there's only a single candidate.
-        if candidates.len() == 1 {
-            return candidates.first().copied();
-        } 
+    /// Given an ordered list of eligible components (as from
+    /// [`TAGEPredictor::alloc_candidates`], closest first), select up to
+    /// `max_alloc` of them to actually allocate into: the closest is
+    /// always taken, and each successively longer one is taken with
+    /// geometrically decreasing probability, reusing the same `1 << idx`
+    /// weighting used to randomly break ties between components.
+    fn select_alloc_targets(&self, candidates: &[usize], max_alloc: usize)
+        -> Vec<usize>
+    {
+        let Some((&first, rest)) = candidates.split_first() else {
+            return Vec::new();
+        };
+        let mut targets = vec![first];
+        let base_weight = 1usize << first;
 
-        // Otherwise, we need some strategy for selecting between multiple 
-        // candidates. In the original paper, the probability scales *down* 
-        // with candidates of increasing history length: given candidates with 
-        // history lengths J and K (where J < K), the candidate J is twice as 
-        // likely to be chosen over K.
-        //
-        // NOTE: In hardware, this is presumably just an LFSR
         let mut rng = rand::thread_rng();
-        let weights: Vec<usize> = candidates.iter().map(|idx| 1 << idx)
-            .collect();
-        let dist = WeightedIndex::new(&weights).unwrap();
-        Some(candidates[dist.sample(&mut rng)])
+        for &idx in rest.iter().take(max_alloc.saturating_sub(1)) {
+            let weight = 1usize << idx;
+            let prob = (weight as f64 / base_weight as f64).min(1.0);
+            if rng.gen_bool(prob) {
+                targets.push(idx);
+            }
+        }
+        targets
     }
 
     /// Update the predictor to account for a misprediction. 
@@ -189,22 +315,27 @@ impl TAGEPredictor {
                 let index = self.comp[idx].get_index(input.clone());
                 let entry = self.comp[idx].get_entry_mut(index);
                 entry.ctr.update(outcome);
-                //entry.decrement_useful();
 
                 self.stat.comp_miss[idx] += 1;
             },
         }
 
-        // Try to allocate a new entry. 
-        // If we've succeeded, initialize the new entry with the correct
-        // outcome [in the weakest state] and reset the 'useful' counter.
+        // Try to allocate new entries into components with a longer
+        // history than the provider: the closest (shortest-history)
+        // eligible component is always taken, and successively longer
+        // ones are taken with geometrically decreasing probability, up
+        // to `cfg.max_alloc` entries total. Each new entry is
+        // initialized with the correct outcome [in the weakest state]
+        // and a cleared 'useful' counter.
         //
-        // All allocation attempts are tracked with an 8-bit counter which is 
-        // incremented on failure and decremented on success. 
-        // When this counter saturates, we reset the state of all 'useful'
-        // counters in an attempt to free up some entries. 
-
-        if let Some(idx) = self.alloc(input.clone(), prediction.provider) {
+        // All allocation attempts are tracked with an 8-bit counter which
+        // is incremented on failure and decremented on success. When
+        // this counter saturates, the 'useful' counters across all
+        // components are aged - see [`UsefulBitPhase`].
+        let candidates = self.alloc_candidates(input.clone(), prediction.provider);
+        let targets = self.select_alloc_targets(&candidates, self.cfg.max_alloc);
+
+        for &idx in &targets {
             let new_index = self.comp[idx].get_index(input.clone());
             let new_tag   = self.comp[idx].get_tag(input.clone());
             let new_entry = self.comp[idx].get_entry_mut(new_index);
@@ -213,16 +344,28 @@ impl TAGEPredictor {
             new_entry.useful = 0;
             new_entry.ctr.set_direction(outcome);
             new_entry.ctr.set_strength(0);
-
             new_entry.stat.branches.insert(input.pc);
-            self.stat.alcs += 1;
-            self.reset_ctr = self.reset_ctr.saturating_add(1);
-        } 
-        else { 
+        }
+
+        *self.stat.allocs_per_miss.entry(targets.len()).or_insert(0) += 1;
+
+        if targets.is_empty() {
             self.stat.failed_alcs += 1;
+            self.reset_ctr = self.reset_ctr.saturating_add(1);
+
+            // No eligible component had a free entry - nudge the
+            // provider's own usefulness down a notch so it eventually
+            // becomes a candidate itself, rather than permanently
+            // blocking allocation for this program counter.
+            if let TAGEProvider::Tagged(idx) = prediction.provider {
+                let index = self.comp[idx].get_index(input.clone());
+                let entry = self.comp[idx].get_entry_mut(index);
+                entry.decrement_useful();
+            }
+        } else {
+            self.stat.alcs += targets.len();
             self.reset_ctr = self.reset_ctr.saturating_sub(1);
         }
-
     }
 
     /// Update the predictor to account for a correct prediction.
@@ -289,12 +432,16 @@ impl TAGEPredictor {
             alt_provider: TAGEProvider::Base,
             alt_outcome: default_outcome,
             alt_idx: base_idx,
-            alt_tag: 0
+            alt_tag: 0,
+            raw_outcome: default_outcome,
+            pseudo_new: false,
+            stage: TAGEStage::Tage,
+            sc_override: false,
         };
 
         // Find the longest-length tagged component that yields a match
         let tagged_iter = tagged_entries.iter().enumerate();
-        for (comp_idx, (entry_idx, entry, tag)) in tagged_iter { 
+        for (comp_idx, (entry_idx, entry, tag)) in tagged_iter {
             if entry.tag_matches(*tag) {
                 result.alt_provider = result.provider;
                 result.alt_outcome  = result.outcome;
@@ -304,16 +451,67 @@ impl TAGEPredictor {
                 result.provider = TAGEProvider::Tagged(comp_idx);
                 result.outcome  = entry.predict();
                 result.idx = *entry_idx;
-                result.tag = *tag; 
+                result.tag = *tag;
+                result.raw_outcome = entry.predict();
+                result.pseudo_new = entry.ctr.magnitude() == 0;
                 break;
             }
         }
+
+        // `useAltPredForNewlyAllocated`: a newly-allocated (weakest-state)
+        // entry is the least trustworthy kind of hit, so when its
+        // USE_ALT_ON_NA counter favors the alternate, trust that instead -
+        // while still recording the real provider in `raw_outcome`/`idx`/
+        // `tag` so `update` can credit/blame the entry that actually fired.
+        if self.cfg.use_alt_on_na && result.pseudo_new {
+            let slot = Self::use_alt_on_na_index(result.idx, result.tag);
+            if self.use_alt_on_na[slot] >= 0 {
+                result.outcome = result.alt_outcome;
+            }
+        }
+
+        // Statistical Corrector: a GEHL-style ensemble that post-processes
+        // the TAGE stage's decision, catching cases systematic to TAGE's
+        // geometric-history design that no single tagged component can
+        // represent. Confidence is read from whichever entry (base or
+        // tagged) provided the TAGE stage's own direction.
+        if let Some(sc) = &self.sc {
+            let confidence = match result.provider {
+                TAGEProvider::Base => base_entry.magnitude(),
+                TAGEProvider::Tagged(idx) => tagged_entries[idx].1.ctr.magnitude(),
+            };
+            let (sc_outcome, sc_override) = sc.predict(input.pc, result.outcome, confidence);
+            if sc_override {
+                result.outcome = sc_outcome;
+                result.stage = TAGEStage::Corrector;
+                result.sc_override = true;
+            }
+        }
+
+        // Loop predictor: given the final say over everything above,
+        // since a confident entry here means we've actually learned the
+        // loop's trip count, which TAGE's geometric history lengths tend
+        // to mispredict right at the loop-exiting iteration.
+        if let Some(lp) = &self.loop_pred {
+            if let Some(loop_outcome) = lp.predict(input.clone()) {
+                result.outcome = loop_outcome;
+                result.stage = TAGEStage::Loop;
+            }
+        }
+
         result
     }
 
-    /// Given a particular prediction and the resolved outcome, update the 
-    /// state of the predictor. 
-    pub fn update(&mut self, 
+    /// Given a particular prediction and the resolved outcome, update the
+    /// state of the predictor.
+    ///
+    /// This is the *commit-time* half of the update flow, paired with the
+    /// speculative, in-flight [`TAGEPredictor::update_history`]: call this
+    /// only once `outcome` is actually known, so table/counter state is
+    /// never trained on a guess. A wrongly-speculated branch is instead
+    /// unwound with [`TAGEPredictor::restore`] before this is ever called
+    /// for it.
+    pub fn update(&mut self,
         input: TAGEInputs, 
         prediction: TAGEPrediction,
         outcome: Outcome
@@ -325,25 +523,136 @@ impl TAGEPredictor {
             self.update_correct(input.clone(), prediction, outcome);
         }
 
-        // Periodically reset *all* of the 'useful' counters across all 
-        // tagged components. 
-        if self.reset_ctr == u8::MAX {
+        // Nudge the USE_ALT_ON_NA counter for this entry whenever the
+        // provider was pseudo-newly-allocated and its raw prediction
+        // actually disagreed with the alternate - that's the only case
+        // that tells us anything about whether trusting the alternate
+        // was the right call.
+        if self.cfg.use_alt_on_na
+            && prediction.pseudo_new
+            && prediction.alt_outcome != prediction.raw_outcome
+        {
+            let slot = Self::use_alt_on_na_index(prediction.idx, prediction.tag);
+            if prediction.alt_outcome == outcome {
+                self.use_alt_on_na[slot] = (self.use_alt_on_na[slot] + 1).min(USE_ALT_ON_NA_MAX);
+            } else {
+                self.use_alt_on_na[slot] = (self.use_alt_on_na[slot] - 1).max(USE_ALT_ON_NA_MIN);
+            }
+        }
+
+        // Train the statistical corrector toward the resolved outcome,
+        // regardless of whether it was consulted this time.
+        if let Some(sc) = &mut self.sc {
+            sc.update(input.pc, prediction.outcome, prediction.sc_override, outcome);
+        }
+
+        // Train the loop predictor toward the resolved outcome,
+        // regardless of whether it was consulted this time, and track
+        // its hit/miss rate on the predictions it actually provided.
+        if let Some(lp) = &mut self.loop_pred {
+            lp.update(input.clone(), outcome);
+            if prediction.stage == TAGEStage::Loop {
+                if prediction.outcome == outcome {
+                    self.stat.loop_hits += 1;
+                } else {
+                    self.stat.loop_miss += 1;
+                }
+            }
+        }
+
+        // Periodically age the 'useful' counters across all tagged
+        // components, one bit-position at a time (graceful two-phase
+        // aging - see [`UsefulBitPhase`]) rather than wiping them out in
+        // a single event.
+        if self.reset_ctr == self.cfg.reset_threshold {
             self.reset_ctr = 0;
             self.stat.resets += 1;
-            for comp in self.comp.iter_mut() {
-                comp.reset_useful_bits();
+            match self.reset_phase {
+                UsefulBitPhase::High => {
+                    self.stat.high_phase_resets += 1;
+                    for comp in self.comp.iter_mut() {
+                        comp.reset_useful_high_bits();
+                    }
+                    self.reset_phase = UsefulBitPhase::Low;
+                },
+                UsefulBitPhase::Low => {
+                    self.stat.low_phase_resets += 1;
+                    for comp in self.comp.iter_mut() {
+                        comp.reset_useful_low_bits();
+                    }
+                    self.reset_phase = UsefulBitPhase::High;
+                },
             }
         }
 
         self.stat.clk += 1;
     }
 
-    /// Given some reference to a [`HistoryRegister`], update the state
-    /// of the folded history register in each tagged component. 
-    pub fn update_history(&mut self, ghr: &HistoryRegister) {
+    /// Speculatively insert `bit` into `ghr` and propagate the update
+    /// into the folded history register of each tagged component (and
+    /// the [`StatisticalCorrector`], if configured).
+    ///
+    /// This is the *speculative, in-flight* half of the update flow: real
+    /// cores predict along a speculative path well before a branch (or
+    /// the branches ahead of it) resolve, so the bit inserted here might
+    /// later turn out to be wrong. A snapshot of `ghr` and every
+    /// component's CSR, taken immediately before this insertion, is
+    /// pushed onto [`TAGEPredictor::history_log`] under the returned
+    /// sequence number - hold onto it if this branch might later need to
+    /// be [`TAGEPredictor::restore`]d.
+    pub fn update_history(&mut self, ghr: &mut HistoryRegister, bit: Outcome) -> u64 {
+        let seq = self.seq;
+        self.seq += 1;
+
+        let checkpoint = HistoryCheckpoint {
+            seq,
+            ghr: ghr.clone(),
+            csr: self.comp.iter().map(|c| c.csr.clone()).collect(),
+            sc_csr: self.sc.as_ref().map(|sc| sc.csr_snapshot()),
+        };
+        if self.history_log.len() == HISTORY_LOG_CAPACITY {
+            self.history_log.pop_front();
+        }
+        self.history_log.push_back(checkpoint);
+
+        ghr.shift_by(1);
+        ghr.data_mut().set(0, bit.into());
+
         for comp in self.comp.iter_mut() {
             comp.csr.update(ghr);
         }
+        if let Some(sc) = &mut self.sc {
+            sc.update_history(ghr);
+        }
+
+        seq
+    }
+
+    /// Rewind `ghr` and every tagged component's folded-history CSR back
+    /// to the state they held immediately before the speculative
+    /// insertion tagged `seq` - e.g. because the branch that caused that
+    /// insertion (or an older one) was discovered to be mispredicted and
+    /// must be squashed.
+    ///
+    /// Also discards the checkpoint for `seq` and every younger one, since
+    /// they all depended on history that no longer exists. Returns `false`
+    /// without changing any state if `seq` has already aged out of the
+    /// bounded [`TAGEPredictor::history_log`].
+    pub fn restore(&mut self, ghr: &mut HistoryRegister, seq: u64) -> bool {
+        let Some(pos) = self.history_log.iter().position(|c| c.seq == seq) else {
+            return false;
+        };
+        let checkpoint = self.history_log[pos].clone();
+        self.history_log.truncate(pos);
+
+        *ghr = checkpoint.ghr;
+        for (comp, csr) in self.comp.iter_mut().zip(checkpoint.csr.into_iter()) {
+            comp.csr = csr;
+        }
+        if let (Some(sc), Some(sc_csr)) = (&mut self.sc, checkpoint.sc_csr) {
+            sc.restore_csrs(sc_csr);
+        }
+        true
     }
 
 }
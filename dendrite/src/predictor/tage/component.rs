@@ -0,0 +1,298 @@
+
+use crate::Outcome;
+use crate::history::*;
+use crate::predictor::*;
+
+/// A base component in the TAGE predictor: a plain bimodal table of
+/// [`SaturatingCounter`], indexed directly off the program counter.
+#[derive(Clone, Debug)]
+pub struct TAGEBaseComponent {
+    pub cfg: TAGEBaseConfig,
+
+    /// Table of counters
+    pub data: Vec<SaturatingCounter>,
+}
+impl PredictorTable for TAGEBaseComponent {
+    type Input<'a> = TAGEInputs;
+    type Index = usize;
+    type Entry = SaturatingCounter;
+
+    fn size(&self) -> usize { self.cfg.size }
+
+    fn get_index(&self, input: TAGEInputs) -> usize {
+        let res = match self.cfg.index_strat {
+            IndexStrategy::FromPc(func) => (func)(self, input.pc),
+        };
+        res & self.index_mask()
+    }
+
+    fn get_entry(&self, idx: usize) -> &SaturatingCounter {
+        let index = idx & self.index_mask();
+        &self.data[index]
+    }
+    fn get_entry_mut(&mut self, idx: usize) -> &mut SaturatingCounter {
+        let index = idx & self.index_mask();
+        &mut self.data[index]
+    }
+}
+
+/// An entry in some [`TAGEComponent`].
+#[derive(Clone, Debug)]
+pub struct TAGEEntry {
+    /// Saturating counter tracking the predicted direction
+    pub ctr: SaturatingCounter,
+
+    /// Number of bits in the 'useful' counter
+    pub useful_bits: usize,
+
+    /// 'Useful' counter, used to decide whether this entry can be
+    /// reclaimed by a later allocation - see
+    /// [`crate::predictor::tage::TAGEPredictor::alloc_candidates`].
+    pub useful: u8,
+
+    /// The tag currently occupying this entry, if any
+    pub tag: Option<usize>,
+
+    /// Runtime stats for this entry
+    pub stat: TAGEEntryStats,
+}
+impl TAGEEntry {
+    pub fn new(ctr: SaturatingCounter, useful_bits: usize) -> Self {
+        Self { ctr, useful_bits, useful: 0, tag: None, stat: TAGEEntryStats::new() }
+    }
+
+    /// Get the current predicted outcome.
+    pub fn predict(&self) -> Outcome {
+        self.ctr.predict()
+    }
+
+    /// Returns true if the provided tag matches this entry.
+    pub fn tag_matches(&self, tag: usize) -> bool {
+        if let Some(val) = self.tag { val == tag } else { false }
+    }
+
+    /// Increment the 'useful' counter, saturating at the configured width.
+    pub fn increment_useful(&mut self) {
+        let max = (1u8 << self.useful_bits) - 1;
+        self.useful = (self.useful + 1).min(max);
+    }
+
+    /// Decrement the 'useful' counter, saturating at zero.
+    pub fn decrement_useful(&mut self) {
+        self.useful = self.useful.saturating_sub(1);
+    }
+
+    /// Invalidate this entry, e.g. immediately before it's reused by a new
+    /// allocation.
+    pub fn invalidate(&mut self, clk: usize) {
+        self.ctr.reset();
+        self.useful = 0;
+        self.tag = None;
+        self.stat.invalidations += 1;
+        self.stat.clk = clk;
+    }
+}
+
+/// A tagged component in the TAGE predictor.
+#[derive(Clone, Debug)]
+pub struct TAGEComponent {
+    pub cfg: TAGEComponentConfig,
+
+    /// Table of entries
+    pub data: Vec<TAGEEntry>,
+
+    /// Folded global history, updated every [`TAGEPredictor::update_history`]
+    /// call - see [`FoldedHistoryRegister`].
+    pub csr: FoldedHistoryRegister,
+}
+impl TAGEComponent {
+    /// Clear the high bit of every entry's 'useful' counter - see
+    /// [`crate::predictor::tage::UsefulBitPhase`].
+    pub fn reset_useful_high_bits(&mut self) {
+        for entry in self.data.iter_mut() {
+            let high_bit = 1u8 << (entry.useful_bits - 1);
+            entry.useful &= !high_bit;
+        }
+    }
+
+    /// Clear the low bit of every entry's 'useful' counter.
+    pub fn reset_useful_low_bits(&mut self) {
+        for entry in self.data.iter_mut() {
+            entry.useful &= !1u8;
+        }
+    }
+
+    /// Fraction of entries that have actually been allocated to some
+    /// branch, i.e. have a tag.
+    pub fn utilization(&self) -> f64 {
+        let used = self.data.iter().filter(|e| e.tag.is_some()).count();
+        used as f64 / self.data.len() as f64
+    }
+}
+
+impl PredictorTable for TAGEComponent {
+    type Input<'a> = TAGEInputs;
+    type Index = usize;
+    type Entry = TAGEEntry;
+
+    fn size(&self) -> usize { self.cfg.size }
+
+    fn get_index(&self, input: TAGEInputs) -> usize {
+        let res = match self.cfg.index_strat {
+            IndexStrategy::FromPc(func) => (func)(self, input.pc),
+        };
+        res & self.index_mask()
+    }
+
+    fn get_entry(&self, idx: usize) -> &TAGEEntry {
+        let index = idx & self.index_mask();
+        &self.data[index]
+    }
+    fn get_entry_mut(&mut self, idx: usize) -> &mut TAGEEntry {
+        let index = idx & self.index_mask();
+        &mut self.data[index]
+    }
+}
+
+impl<'a> TaggedPredictorTable<'a> for TAGEComponent {
+    type Tag<'b> = usize;
+
+    fn get_tag(&self, input: TAGEInputs) -> usize {
+        match self.cfg.tag_strat {
+            TagStrategy::FromPc(func) => (func)(self, input.pc),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ctr_cfg() -> SaturatingCounterConfig {
+        SaturatingCounterConfig {
+            max_t_state: 3,
+            max_n_state: 3,
+            default_state: Outcome::N,
+        }
+    }
+
+    fn base_component(size: usize) -> TAGEBaseComponent {
+        TAGEBaseConfig {
+            ctr: ctr_cfg(),
+            size,
+            index_strat: IndexStrategy::FromPc(|_t, pc| pc),
+        }.build()
+    }
+
+    fn tagged_component(size: usize, ghr_range: std::ops::RangeInclusive<usize>) -> TAGEComponent {
+        TAGEComponentConfig {
+            size,
+            ghr_range,
+            tag_bits: 8,
+            useful_bits: 2,
+            index_strat: IndexStrategy::FromPc(|_t, pc| pc),
+            tag_strat: TagStrategy::FromPc(|_t, pc| pc.wrapping_add(1000)),
+            ctr: ctr_cfg(),
+        }.build()
+    }
+
+    #[test]
+    fn get_index_masks_down_to_the_table_size() {
+        let c = base_component(16);
+        assert_eq!(c.get_index(TAGEInputs { pc: 0x1230 }), 0x0);
+        assert_eq!(c.get_index(TAGEInputs { pc: 0x123f }), 0xf);
+    }
+
+    #[test]
+    fn get_entry_and_get_entry_mut_wrap_the_raw_index_through_the_mask() {
+        let mut c = base_component(16);
+        // An out-of-range raw index should wrap via the mask, same as get_index.
+        c.get_entry_mut(0x20).strengthen();
+        assert_eq!(c.get_entry(0x0).magnitude(), 1);
+    }
+
+    #[test]
+    fn tagged_component_dispatches_index_and_tag_independently() {
+        let c = tagged_component(16, 0..=7);
+        let input = TAGEInputs { pc: 3 };
+        assert_eq!(c.get_index(input.clone()), 3);
+        assert_eq!(c.get_tag(input), 1003);
+    }
+
+    #[test]
+    fn increment_and_decrement_useful_saturate_at_the_configured_width() {
+        let mut entry = TAGEEntry::new(ctr_cfg().build(), 2);
+        assert_eq!(entry.useful, 0);
+
+        entry.decrement_useful();
+        assert_eq!(entry.useful, 0);
+
+        for expected in 1..=3 {
+            entry.increment_useful();
+            assert_eq!(entry.useful, expected);
+        }
+        // Already at the 2-bit limit - another increment can't go further.
+        entry.increment_useful();
+        assert_eq!(entry.useful, 3);
+    }
+
+    #[test]
+    fn tag_matches_is_false_until_a_tag_is_assigned() {
+        let mut entry = TAGEEntry::new(ctr_cfg().build(), 2);
+        assert!(!entry.tag_matches(42));
+        entry.tag = Some(42);
+        assert!(entry.tag_matches(42));
+        assert!(!entry.tag_matches(43));
+    }
+
+    #[test]
+    fn invalidate_resets_the_counter_useful_and_tag_and_bumps_stats() {
+        let mut entry = TAGEEntry::new(ctr_cfg().build(), 2);
+        entry.ctr.strengthen();
+        entry.increment_useful();
+        entry.tag = Some(7);
+        entry.stat.branches.insert(7);
+
+        entry.invalidate(42);
+
+        assert_eq!(entry.predict(), Outcome::N);
+        assert_eq!(entry.useful, 0);
+        assert_eq!(entry.tag, None);
+        assert_eq!(entry.stat.invalidations, 1);
+        assert_eq!(entry.stat.clk, 42);
+        // Invalidation doesn't erase the historical record of which branches
+        // have aliased into this entry.
+        assert!(!entry.stat.was_unused());
+    }
+
+    #[test]
+    fn utilization_reflects_the_fraction_of_tagged_entries() {
+        let mut c = tagged_component(4, 0..=7);
+        assert_eq!(c.utilization(), 0.0);
+
+        c.data[0].tag = Some(1);
+        c.data[2].tag = Some(2);
+        assert_eq!(c.utilization(), 0.5);
+    }
+
+    #[test]
+    fn reset_useful_high_and_low_bits_clear_only_their_own_bit() {
+        let mut c = tagged_component(4, 0..=7);
+        for entry in c.data.iter_mut() {
+            entry.useful = 0b11;
+        }
+
+        c.reset_useful_high_bits();
+        for entry in c.data.iter() {
+            assert_eq!(entry.useful, 0b01);
+        }
+
+        for entry in c.data.iter_mut() {
+            entry.useful = 0b11;
+        }
+        c.reset_useful_low_bits();
+        for entry in c.data.iter() {
+            assert_eq!(entry.useful, 0b10);
+        }
+    }
+}
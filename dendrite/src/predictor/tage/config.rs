@@ -1,5 +1,6 @@
 
 use crate::predictor::*;
+use super::USE_ALT_ON_NA_SIZE;
 use std::ops::RangeInclusive;
 
 /// Configuration for a [`TAGEBaseComponent`].
@@ -92,12 +93,41 @@ pub struct TAGEConfig {
 
     /// Tagged component configurations
     pub comp: Vec<TAGEComponentConfig>,
+
+    /// When set, a newly-allocated (weakest-state) tagged entry that
+    /// disagrees with the alternate prediction defers to the alternate
+    /// instead, per-entry, according to the `USE_ALT_ON_NA` counters -
+    /// see [`TAGEPredictor::predict`].
+    pub use_alt_on_na: bool,
+
+    /// When set, a [`StatisticalCorrector`] post-processes the TAGE
+    /// stage's decision - see [`TAGEPredictor::predict`].
+    pub sc: Option<SCConfig>,
+
+    /// When set, a [`LoopPredictor`] is given the final say over TAGE
+    /// (and SC) whenever it's confident - see [`TAGEPredictor::predict`].
+    pub loop_pred: Option<LoopConfig>,
+
+    /// Value the allocation counter must reach to trigger a 'useful'-bit
+    /// aging event - see [`UsefulBitPhase`]. That counter moves only on
+    /// allocation attempts in [`TAGEPredictor::update`] (up on a failed
+    /// allocation, down on a successful one); it does not count calls.
+    pub reset_threshold: u8,
+
+    /// Maximum number of components to allocate into per misprediction -
+    /// see [`TAGEPredictor::select_alloc_targets`].
+    pub max_alloc: usize,
 }
 impl TAGEConfig {
     pub fn new(base: TAGEBaseConfig) -> Self {
         Self {
             base,
             comp: Vec::new(),
+            use_alt_on_na: false,
+            sc: None,
+            loop_pred: None,
+            reset_threshold: u8::MAX,
+            max_alloc: 2,
         }
     }
 
@@ -129,12 +159,18 @@ impl TAGEConfig {
             .collect::<Vec<TAGEComponent>>();
         let base = self.base.build();
         let stat = TAGEStats::new(comp.len());
-        TAGEPredictor { 
-            cfg, 
-            base, 
-            comp, 
-            stat, 
+        TAGEPredictor {
+            cfg,
+            base,
+            comp,
+            stat,
             reset_ctr: 0,
+            reset_phase: UsefulBitPhase::High,
+            use_alt_on_na: [0i8; USE_ALT_ON_NA_SIZE],
+            sc: self.sc.map(|c| c.build()),
+            loop_pred: self.loop_pred.map(|c| c.build()),
+            seq: 0,
+            history_log: std::collections::VecDeque::new(),
         }
     }
 }
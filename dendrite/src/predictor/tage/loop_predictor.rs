@@ -0,0 +1,136 @@
+//! Implementation of a loop predictor (as in TAGE-SC-L / IMLI), which
+//! overrides a [`crate::predictor::tage::TAGEPredictor`] entirely on
+//! regularly-iterating loop branches that TAGE's geometric history
+//! lengths tend to mispredict on the final, loop-exiting iteration.
+
+use crate::Outcome;
+use crate::predictor::*;
+
+use super::TAGEInputs;
+
+/// Confidence level at which a [LoopEntry] is trusted to override TAGE.
+const LOOP_CONFIDENCE_MAX: u8 = 3;
+
+/// An entry in a [LoopPredictor].
+///
+/// Assumes the associated branch is the backward branch of a regularly
+/// iterating loop: taken on every iteration except the last, where it is
+/// not-taken (the loop exits).
+#[derive(Clone, Copy, Debug)]
+pub struct LoopEntry {
+    /// A partial tag used to detect aliasing within the table.
+    pub tag: Option<usize>,
+
+    /// The number of taken iterations observed the last time this loop ran.
+    pub past_iter_count: usize,
+
+    /// The number of taken iterations observed so far in the current run.
+    pub current_iter: usize,
+
+    /// Confidence that [LoopEntry::past_iter_count] correctly predicts
+    /// the length of the next run of the loop. Saturates at
+    /// [LOOP_CONFIDENCE_MAX].
+    pub confidence: u8,
+
+    /// Number of completed runs of the loop. A tag mismatch replaces the
+    /// entry whatever its age.
+    pub age: u8,
+}
+impl LoopEntry {
+    fn new() -> Self {
+        Self { tag: None, past_iter_count: 0, current_iter: 0, confidence: 0, age: 0 }
+    }
+}
+
+/// Configuration for a [`LoopPredictor`].
+#[derive(Clone, Debug)]
+pub struct LoopConfig {
+    /// Number of entries
+    pub size: usize,
+
+    /// Number of tag bits
+    pub tag_bits: usize,
+
+    /// Strategy for indexing into the table.
+    pub index_strat: IndexStrategy<LoopPredictor>,
+
+    /// Strategy for creating tags.
+    pub tag_strat: TagStrategy<LoopPredictor>,
+}
+impl LoopConfig {
+    /// Use this configuration to create a new [`LoopPredictor`].
+    pub fn build(self) -> LoopPredictor {
+        assert!(self.size.is_power_of_two());
+        LoopPredictor {
+            data: vec![LoopEntry::new(); self.size],
+            cfg: self,
+        }
+    }
+}
+
+/// A loop predictor, as in the L-TAGE/IMLI design: a small table of
+/// [LoopEntry] used to override [`crate::predictor::tage::TAGEPredictor`]
+/// entirely on regularly-iterating loop branches.
+#[derive(Clone, Debug)]
+pub struct LoopPredictor {
+    cfg: LoopConfig,
+    data: Vec<LoopEntry>,
+}
+impl LoopPredictor {
+    fn get_index(&self, input: TAGEInputs) -> usize {
+        match self.cfg.index_strat {
+            IndexStrategy::FromPc(f) => f(self, input.pc) & (self.cfg.size - 1),
+        }
+    }
+
+    fn get_tag(&self, input: TAGEInputs) -> usize {
+        match self.cfg.tag_strat {
+            TagStrategy::FromPc(f) => f(self, input.pc) & ((1 << self.cfg.tag_bits) - 1),
+        }
+    }
+
+    /// Return a direction, overriding TAGE, if and only if the entry for
+    /// this input has a matching tag and maximal confidence. Otherwise,
+    /// return [None] and defer to TAGE.
+    pub fn predict(&self, input: TAGEInputs) -> Option<Outcome> {
+        let tag = self.get_tag(input.clone());
+        let entry = &self.data[self.get_index(input)];
+        if entry.tag != Some(tag) || entry.confidence < LOOP_CONFIDENCE_MAX {
+            return None;
+        }
+        if entry.current_iter < entry.past_iter_count {
+            Some(Outcome::T)
+        } else {
+            Some(Outcome::N)
+        }
+    }
+
+    /// Update the entry for this input with the resolved branch
+    /// `outcome`. Allocates (resetting) the entry on a tag mismatch.
+    pub fn update(&mut self, input: TAGEInputs, outcome: Outcome) {
+        let tag = self.get_tag(input.clone());
+        let idx = self.get_index(input);
+        let entry = &mut self.data[idx];
+        if entry.tag != Some(tag) {
+            *entry = LoopEntry { tag: Some(tag), ..LoopEntry::new() };
+        }
+
+        match outcome {
+            // Still iterating through the loop body.
+            Outcome::T => entry.current_iter += 1,
+
+            // The loop has exited - check whether the observed iteration
+            // count matched what we expected.
+            Outcome::N => {
+                if entry.current_iter == entry.past_iter_count {
+                    entry.confidence = (entry.confidence + 1).min(LOOP_CONFIDENCE_MAX);
+                } else {
+                    entry.confidence = 0;
+                    entry.past_iter_count = entry.current_iter;
+                }
+                entry.current_iter = 0;
+                entry.age = entry.age.saturating_add(1);
+            },
+        }
+    }
+}
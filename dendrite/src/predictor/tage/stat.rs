@@ -16,21 +16,53 @@ pub struct TAGEStats {
     /// Misses in the tagged components
     pub comp_miss: Vec<usize>,
 
-    /// Number of 'useful' counter resets
+    /// Hits in the base component
+    pub base_hits: usize,
+
+    /// Hits in the tagged components
+    pub comp_hits: Vec<usize>,
+
+    /// Number of 'useful'-bit aging events (either phase)
     pub resets: usize,
 
+    /// Number of aging events that cleared the high bit - see
+    /// [`crate::predictor::tage::UsefulBitPhase`].
+    pub high_phase_resets: usize,
+
+    /// Number of aging events that cleared the low bit.
+    pub low_phase_resets: usize,
+
     /// Number of updates
     pub clk: usize,
+
+    /// Hits where the loop predictor was the final provider - see
+    /// [`crate::predictor::tage::LoopPredictor`].
+    pub loop_hits: usize,
+
+    /// Misses where the loop predictor was the final provider.
+    pub loop_miss: usize,
+
+    /// Distribution of the number of entries allocated per misprediction,
+    /// keyed by the number of entries - see
+    /// [`crate::predictor::tage::TAGEPredictor::select_alloc_targets`].
+    pub allocs_per_miss: HashMap<usize, usize>,
 }
 impl TAGEStats {
-    pub fn new(num_comp: usize) -> Self { 
+    pub fn new(num_comp: usize) -> Self {
         Self {
             alcs: 0,
             failed_alcs: 0,
             base_miss: 0,
             comp_miss: vec![0; num_comp],
+            base_hits: 0,
+            comp_hits: vec![0; num_comp],
             resets: 0,
+            high_phase_resets: 0,
+            low_phase_resets: 0,
             clk: 0,
+            loop_hits: 0,
+            loop_miss: 0,
+            allocs_per_miss: HashMap::new(),
         }
     }
 }
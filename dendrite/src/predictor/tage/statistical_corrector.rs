@@ -0,0 +1,191 @@
+//! Implementation of a "Statistical Corrector" (SC) stage, which
+//! post-processes predictions from a [`crate::predictor::tage::TAGEPredictor`].
+
+use std::ops::RangeInclusive;
+
+use crate::Outcome;
+use crate::history::*;
+
+/// Lower bound on [StatisticalCorrector]'s adaptive threshold, so it can
+/// never collapse to the point where every disagreement overrides TAGE.
+const SC_THETA_MIN: i32 = 2;
+
+/// One bank of signed, saturating counters in a [StatisticalCorrector],
+/// indexed by folding some piece of state (history or the branch PC)
+/// down to `log_size` bits.
+#[derive(Clone, Debug)]
+pub struct SCBank {
+    data: Vec<i8>,
+    log_size: usize,
+}
+impl SCBank {
+    pub fn new(log_size: usize) -> Self {
+        Self { data: vec![0i8; 1 << log_size], log_size }
+    }
+
+    fn index(&self, hash: usize) -> usize { hash & ((1 << self.log_size) - 1) }
+
+    /// Read the counter selected by `hash`.
+    pub fn get(&self, hash: usize) -> i8 {
+        self.data[self.index(hash)]
+    }
+
+    /// Train the counter selected by `hash` toward `outcome`.
+    pub fn train(&mut self, hash: usize, outcome: Outcome) {
+        let idx = self.index(hash);
+        match outcome {
+            Outcome::T => self.data[idx] = self.data[idx].saturating_add(1),
+            Outcome::N => self.data[idx] = self.data[idx].saturating_sub(1),
+        }
+    }
+}
+
+/// Configuration for a [`StatisticalCorrector`].
+#[derive(Clone, Debug)]
+pub struct SCConfig {
+    /// Global-history ranges folded into each GEHL bank.
+    pub gehl_ranges: Vec<RangeInclusive<usize>>,
+
+    /// Size (in bits) of each GEHL bank.
+    pub bank_log_size: usize,
+
+    /// Size (in bits) of the PC bank.
+    pub pc_log_size: usize,
+
+    /// Initial decision threshold.
+    pub theta_init: i32,
+}
+impl SCConfig {
+    /// Use this configuration to create a new [`StatisticalCorrector`].
+    pub fn build(self) -> StatisticalCorrector {
+        let gehl_csrs = self.gehl_ranges.iter()
+            .map(|range| FoldedHistoryRegister::new(self.bank_log_size, range.clone()))
+            .collect();
+        let gehl_banks = self.gehl_ranges.iter()
+            .map(|_| SCBank::new(self.bank_log_size))
+            .collect();
+        StatisticalCorrector {
+            gehl_csrs,
+            gehl_banks,
+            pc_bank: SCBank::new(self.pc_log_size),
+            theta: self.theta_init,
+            corrections: 0,
+            correct_corrections: 0,
+        }
+    }
+}
+
+/// The "statistical corrector" (SC) stage from TAGE-SC-L: a GEHL-style
+/// ensemble of signed-counter banks that post-processes a TAGE
+/// prediction, catching cases systematic to TAGE's geometric-history
+/// design that no single tagged component can represent.
+///
+/// See "A 64kbits ISL-TAGE branch predictor" (Seznec, 2011) and
+/// "TAGE-SC-L branch predictors" (Seznec, 2014).
+pub struct StatisticalCorrector {
+    /// Folded-history CSRs, one per GEHL bank, each at a different
+    /// geometric history length.
+    gehl_csrs: Vec<FoldedHistoryRegister>,
+
+    /// GEHL banks, indexed by `gehl_csrs`' folded output.
+    gehl_banks: Vec<SCBank>,
+
+    /// Bank biased purely by the program counter.
+    pc_bank: SCBank,
+
+    /// Adaptive decision threshold: the signed sum must reach this
+    /// magnitude (in the direction opposite the TAGE provider) to
+    /// override it.
+    theta: i32,
+
+    /// Number of predictions where SC overrode the TAGE provider.
+    corrections: u64,
+
+    /// Number of those overrides that turned out to be correct.
+    correct_corrections: u64,
+}
+impl StatisticalCorrector {
+    /// Re-derive the signed sum behind a prediction, minus the provider
+    /// confidence term (which isn't retained between [`StatisticalCorrector::predict`]
+    /// and [`StatisticalCorrector::update`]).
+    fn margin(&self, pc: usize) -> i32 {
+        let mut sum: i32 = 0;
+        for (csr, bank) in self.gehl_csrs.iter().zip(self.gehl_banks.iter()) {
+            sum += bank.get(csr.output_usize()) as i32;
+        }
+        sum += self.pc_bank.get(pc) as i32;
+        sum
+    }
+
+    /// Given the TAGE stage's own prediction and its confidence (the
+    /// magnitude of the counter that produced it), decide whether to
+    /// keep it or flip it. Returns the final outcome and whether SC
+    /// overrode the TAGE stage.
+    pub fn predict(&self, pc: usize, provider_outcome: Outcome, provider_confidence: u8)
+        -> (Outcome, bool)
+    {
+        let mut sum = self.margin(pc);
+        sum += match provider_outcome {
+            Outcome::T => provider_confidence as i32,
+            Outcome::N => -(provider_confidence as i32),
+        };
+
+        let sc_outcome = if sum >= 0 { Outcome::T } else { Outcome::N };
+        let sc_override = sc_outcome != provider_outcome && sum.abs() >= self.theta;
+        let outcome = if sc_override { sc_outcome } else { provider_outcome };
+        (outcome, sc_override)
+    }
+
+    /// Train every selected bank counter toward the resolved `outcome`,
+    /// and adapt the decision threshold based on whether the prediction
+    /// made with `sc_override` was correct.
+    pub fn update(&mut self, pc: usize, predicted: Outcome, sc_override: bool, outcome: Outcome) {
+        if sc_override {
+            self.corrections += 1;
+            if predicted == outcome {
+                self.correct_corrections += 1;
+            }
+        }
+
+        if predicted != outcome {
+            self.theta = self.theta.saturating_add(1);
+        } else {
+            let sum = self.margin(pc);
+            if sum.abs() <= self.theta {
+                self.theta = (self.theta - 1).max(SC_THETA_MIN);
+            }
+        }
+
+        for (csr, bank) in self.gehl_csrs.iter().zip(self.gehl_banks.iter_mut()) {
+            bank.train(csr.output_usize(), outcome);
+        }
+        self.pc_bank.train(pc, outcome);
+    }
+
+    /// Update all of SC's folded-history CSRs with the newest global
+    /// history.
+    pub fn update_history(&mut self, ghr: &HistoryRegister) {
+        for csr in self.gehl_csrs.iter_mut() {
+            csr.update(ghr);
+        }
+    }
+
+    /// Snapshot every GEHL CSR, for [`crate::predictor::tage::TAGEPredictor::update_history`]
+    /// to stash alongside the rest of a [`crate::predictor::tage::TAGEPredictor`]'s
+    /// speculative history state.
+    pub(crate) fn csr_snapshot(&self) -> Vec<FoldedHistoryRegister> {
+        self.gehl_csrs.clone()
+    }
+
+    /// Restore every GEHL CSR from a snapshot taken by [`Self::csr_snapshot`]
+    /// - see [`crate::predictor::tage::TAGEPredictor::restore`].
+    pub(crate) fn restore_csrs(&mut self, csrs: Vec<FoldedHistoryRegister>) {
+        self.gehl_csrs = csrs;
+    }
+
+    /// Number of predictions where SC overrode the TAGE stage.
+    pub fn corrections(&self) -> u64 { self.corrections }
+
+    /// Number of SC overrides that were correct.
+    pub fn correct_corrections(&self) -> u64 { self.correct_corrections }
+}
@@ -1,6 +1,7 @@
 //! Helpers for collecting statistics.
 
 use std::collections::*;
+use std::cmp::Reverse;
 use crate::branch::*;
 use bitvec::prelude::*;
 use itertools::*;
@@ -54,6 +55,7 @@ impl BranchStats {
         let data = self.get_mut(record.pc);
         data.occ += 1;
         data.pat.push(outcome.into());
+        data.hit_pat.push(hit);
         if hit { data.hits += 1; }
     }
 
@@ -95,32 +97,45 @@ impl BranchStats {
     }
 
 
+    /// Returns the `n` branches with the highest occurrence count, sorted
+    /// from most- to least-common.
+    ///
+    /// Keeps a bounded min-heap of capacity `n` (keyed on `occ`) instead of
+    /// sorting the whole map, so this stays cheap even when `n` is small
+    /// relative to [BranchStats::num_unique_branches].
     pub fn get_common_branches(&self, n: usize) -> Vec<(usize, &BranchData)> {
-        let iter = self.data.iter()
-            .sorted_by(|x, y| { x.1.occ.partial_cmp(&y.1.occ).unwrap() })
-            .rev()
-            .take(n);
-        let res: Vec<(usize, &BranchData)> = iter.map(|(pc, s)| (*pc, s))
-            .collect();
-        res
+        let mut heap: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::with_capacity(n + 1);
+        for (pc, s) in self.data.iter() {
+            heap.push(Reverse((s.occ, *pc)));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec().into_iter()
+            .map(|Reverse((_, pc))| (pc, self.data.get(&pc).unwrap()))
+            .collect()
     }
 
-    pub fn get_low_rate_branches(&self, n: usize) 
-        -> Vec<(usize, &BranchData)> 
+    /// Returns the `n` most-common branches (among those occurring more than
+    /// 100 times with a hit rate no better than 55%), sorted from most- to
+    /// least-common.
+    ///
+    /// Uses the same bounded min-heap strategy as [BranchStats::get_common_branches].
+    pub fn get_low_rate_branches(&self, n: usize)
+        -> Vec<(usize, &BranchData)>
     {
-        let iter = self.data.iter()
-            .filter(|(_, s)| {
-                s.occ > 100 && s.hit_rate() <= 0.55
-            })
-            //.sorted_by(|x, y| { 
-            //    x.1.hit_rate().partial_cmp(&y.1.hit_rate()).unwrap()
-            //})
-            .sorted_by(|x, y| { x.1.occ.partial_cmp(&y.1.occ).unwrap() })
-            .rev()
-            .take(n);
-        let res: Vec<(usize, &BranchData)> = iter.map(|(pc, s)| (*pc,s))
-            .collect();
-        res
+        let mut heap: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::with_capacity(n + 1);
+        for (pc, s) in self.data.iter() {
+            if s.occ > 100 && s.hit_rate() <= 0.55 {
+                heap.push(Reverse((s.occ, *pc)));
+                if heap.len() > n {
+                    heap.pop();
+                }
+            }
+        }
+        heap.into_sorted_vec().into_iter()
+            .map(|Reverse((_, pc))| (pc, self.data.get(&pc).unwrap()))
+            .collect()
     }
 
 }
@@ -135,6 +150,10 @@ pub struct BranchData {
 
     /// Record of all observed outcomes for this branch.
     pub pat: BitVec,
+
+    /// Record of whether each occurrence was predicted correctly,
+    /// parallel to `pat`.
+    pub hit_pat: BitVec,
 }
 impl BranchData {
     pub fn new() -> Self {
@@ -142,6 +161,7 @@ impl BranchData {
             occ: 0,
             hits: 0,
             pat: BitVec::new(),
+            hit_pat: BitVec::new(),
         }
     }
 
@@ -176,6 +196,104 @@ impl BranchData {
         let res = -(p_t * p_t.log2() + p_f * p_f.log2());
         if res.is_nan() { 0.0 } else { res }
     }
+
+    /// Return the number of "taken" outcomes within `[i, j)`.
+    ///
+    /// Builds a cumulative popcount over all of `pat` on every call, then
+    /// answers with a single subtraction. [BranchData::windowed_entropy]
+    /// does not call this; it builds one [PrefixRank] and reuses it.
+    pub fn taken_in_range(&self, i: usize, j: usize) -> usize {
+        PrefixRank::build(&self.pat).count_in_range(i, j)
+    }
+
+    /// Return the hit rate over occurrences `[i, j)`, using the same
+    /// cumulative-popcount approach as [BranchData::taken_in_range] but
+    /// over `hit_pat` instead of `pat`.
+    pub fn hit_rate_in_range(&self, i: usize, j: usize) -> f64 {
+        PrefixRank::build(&self.hit_pat).count_in_range(i, j) as f64 / (j - i) as f64
+    }
+
+    /// Slide a fixed-size window across the recorded outcome history and
+    /// return the per-window Shannon entropy series, letting callers
+    /// detect a branch that changes regime mid-trace instead of only
+    /// seeing a single blurred average over the whole history.
+    pub fn windowed_entropy(&self, window_len: usize, stride: usize) -> Vec<f64> {
+        let pr = PrefixRank::build(&self.pat);
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i + window_len <= self.pat.len() {
+            let n_t = pr.count_in_range(i, i + window_len);
+            let n_f = window_len - n_t;
+            let p_t = n_t as f64 / window_len as f64;
+            let p_f = n_f as f64 / window_len as f64;
+            let e = -(p_t * p_t.log2() + p_f * p_f.log2());
+            out.push(if e.is_nan() { 0.0 } else { e });
+            i += stride;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn branch(occ: usize, hits: usize) -> BranchData {
+        BranchData { occ, hits, pat: BitVec::new(), hit_pat: BitVec::new() }
+    }
+
+    #[test]
+    fn get_common_branches_returns_the_n_highest_occurrence_counts_descending() {
+        let mut stats = BranchStats::new();
+        stats.data.insert(0x10, branch(5, 0));
+        stats.data.insert(0x20, branch(50, 0));
+        stats.data.insert(0x30, branch(20, 0));
+        stats.data.insert(0x40, branch(100, 0));
+
+        let top = stats.get_common_branches(2);
+        let pcs: Vec<usize> = top.iter().map(|(pc, _)| *pc).collect();
+        assert_eq!(pcs, vec![0x40, 0x20]);
+    }
+
+    #[test]
+    fn get_low_rate_branches_filters_by_occurrence_and_hit_rate_threshold() {
+        let mut stats = BranchStats::new();
+        // Below the occurrence threshold - excluded even with a low hit rate.
+        stats.data.insert(0x10, branch(50, 0));
+        // Above the occurrence threshold but hit rate too high - excluded.
+        stats.data.insert(0x20, branch(200, 180));
+        // Eligible: occ > 100 and hit_rate <= 0.55.
+        stats.data.insert(0x30, branch(150, 50));
+        stats.data.insert(0x40, branch(300, 100));
+
+        let low = stats.get_low_rate_branches(5);
+        let pcs: Vec<usize> = low.iter().map(|(pc, _)| *pc).collect();
+        assert_eq!(pcs, vec![0x40, 0x30]);
+    }
+}
+
+/// A cumulative popcount over a [BitSlice], answering "how many set bits
+/// in `[i, j)`?" in O(1) after an O(n) build, instead of rescanning the
+/// window on every query.
+struct PrefixRank {
+    cum: Vec<usize>,
+}
+impl PrefixRank {
+    fn build(bits: &BitSlice) -> Self {
+        let mut cum = Vec::with_capacity(bits.len() + 1);
+        cum.push(0);
+        let mut acc = 0;
+        for b in bits.iter() {
+            acc += *b as usize;
+            cum.push(acc);
+        }
+        Self { cum }
+    }
+
+    /// Number of set bits within `[i, j)`.
+    fn count_in_range(&self, i: usize, j: usize) -> usize {
+        self.cum[j] - self.cum[i]
+    }
 }
 
 
@@ -1,10 +1,14 @@
 
 pub mod assembler;
+pub mod format;
+
+pub use format::{TraceWriter, TraceReader, TraceFormatError, Endianness, text_line, write_text};
 
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read, BufReader};
 use std::path::Path;
 use crate::branch::*;
+use crate::analysis::WaveletMatrix;
 
 pub struct BinaryTraceSet {
     /// A list of filenames
@@ -14,8 +18,8 @@ pub struct BinaryTraceSet {
     pub next: usize,
 }
 impl BinaryTraceSet {
-    pub fn new() -> Self { 
-        Self { 
+    pub fn new() -> Self {
+        Self {
             files: Vec::new(),
             cur: 0,
             next: 1,
@@ -25,7 +29,7 @@ impl BinaryTraceSet {
     pub fn new_from_slice(strings: &[String]) -> Self {
         let mut files = Vec::new();
         files.extend_from_slice(strings);
-        Self { 
+        Self {
             files,
             cur: 0,
             next: 1,
@@ -37,22 +41,71 @@ impl BinaryTraceSet {
     }
 }
 impl Iterator for BinaryTraceSet {
-    type Item = BinaryTrace;
+    type Item = io::Result<BinaryTrace>;
     fn next(&mut self) -> Option<Self::Item> {
         if self.cur == self.files.len() {
             return None;
         } else {
             let cur = self.cur;
-            let name = Path::new(&self.files[cur])
-                .file_name().unwrap()
-                .to_str().unwrap();
-            let trace = BinaryTrace::from_file(&self.files[cur], name);
             self.cur += 1;
-            Some(trace)
+            let path = &self.files[cur];
+            let name = match Path::new(path).file_name().and_then(|n| n.to_str()) {
+                Some(name) => name.to_string(),
+                None => {
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("couldn't extract a file name from '{}'", path),
+                    )));
+                },
+            };
+            Some(BinaryTrace::from_file(path, &name))
         }
     }
 }
 
+/// A streaming reader over a [BinaryTrace] file, yielding one [BranchRecord]
+/// at a time instead of holding the whole trace in memory.
+///
+/// Unlike [BinaryTrace::from_file], this never slurps the file into a
+/// buffer up front, so it's suitable for traces much larger than RAM.
+pub struct BinaryTraceReader {
+    reader: BufReader<File>,
+}
+impl BinaryTraceReader {
+    /// Open a trace file for streaming access.
+    pub fn from_file(path: &str) -> io::Result<Self> {
+        let f = File::open(path)?;
+        Ok(Self { reader: BufReader::new(f) })
+    }
+}
+impl Iterator for BinaryTraceReader {
+    type Item = io::Result<BranchRecord>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0u8; std::mem::size_of::<BranchRecord>()];
+
+        // Peek a single byte first so a clean EOF at a record boundary
+        // ends iteration instead of being reported as an error.
+        let first = match self.reader.read(&mut buf[..1]) {
+            Ok(0) => return None,
+            Ok(n) => n,
+            Err(e) => return Some(Err(e)),
+        };
+
+        if let Err(e) = self.reader.read_exact(&mut buf[first..]) {
+            if e.kind() != io::ErrorKind::UnexpectedEof {
+                return Some(Err(e));
+            }
+            return Some(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "trace file ended in the middle of a record",
+            )));
+        }
+
+        let record = unsafe { std::ptr::read(buf.as_ptr() as *const BranchRecord) };
+        Some(Ok(record))
+    }
+}
+
 
 /// A trace generated with the 'dendrite' client for DynamoRIO. 
 pub struct BinaryTrace {
@@ -63,21 +116,33 @@ pub struct BinaryTrace {
 }
 impl BinaryTrace {
 
-    /// Create a [BinaryTrace] from a file.
-    /// NOTE: We aren't validating input at all
-    pub fn from_file(path: &str, name: &str) -> Self {
-        let mut f = File::open(path).unwrap();
-        let len = std::fs::metadata(path).unwrap().len() as usize;
-        assert!(len % std::mem::size_of::<BranchRecord>() == 0);
+    /// Create a [BinaryTrace] from a file, reading the whole thing into
+    /// memory up front.
+    ///
+    /// This is the "mmap-style" path meant for traces small enough to fit
+    /// comfortably in RAM; for multi-gigabyte traces, stream the file
+    /// instead with a [BinaryTraceReader].
+    pub fn from_file(path: &str, name: &str) -> io::Result<Self> {
+        let mut f = File::open(path)?;
+        let len = std::fs::metadata(path)?.len() as usize;
+        if len % std::mem::size_of::<BranchRecord>() != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "'{}' has length {} which isn't a multiple of the record size ({})",
+                    path, len, std::mem::size_of::<BranchRecord>()
+                ),
+            ));
+        }
 
         let num_entries = len / std::mem::size_of::<BranchRecord>();
         let mut data = vec![0; len];
-        f.read(&mut data).unwrap();
-        Self { 
-            data, 
+        f.read_exact(&mut data)?;
+        Ok(Self {
+            data,
             num_entries,
             name: name.to_string(),
-        }
+        })
     }
 
     /// Return the number of records
@@ -113,4 +178,98 @@ impl BinaryTrace {
 
 }
 
+/// A succinct index over a [BinaryTrace], built from a [WaveletMatrix]
+/// over PC values and a second one over target addresses, answering
+/// windowed range queries without rescanning the trace: "how many times
+/// did PC X execute within records `[l, r)`?", "what's the `k`-th most
+/// frequent target in `[l, r)`?", and "how many distinct branch sites
+/// appear in `[l, r)`?".
+pub struct TraceIndex {
+    pc: WaveletMatrix,
+    tgt: WaveletMatrix,
+}
+impl TraceIndex {
+    /// Build an index over every record in `trace`.
+    pub fn build(trace: &BinaryTrace) -> Self {
+        let records = trace.as_slice();
+        Self {
+            pc: WaveletMatrix::from_records(records, |r| r.pc),
+            tgt: WaveletMatrix::from_records(records, |r| r.tgt),
+        }
+    }
+
+    /// Return the number of times `pc` occurs within record range `range`.
+    pub fn pc_count(&self, range: std::ops::Range<usize>, pc: usize) -> usize {
+        self.pc.range_freq(range, pc)
+    }
+
+    /// Return the number of distinct PC values within `range`.
+    pub fn num_distinct_pcs(&self, range: std::ops::Range<usize>) -> usize {
+        self.pc.count_distinct(range)
+    }
+
+    /// Return the number of records within `range` whose PC is `< x`,
+    /// without rescanning the window.
+    pub fn pc_count_lt(&self, range: std::ops::Range<usize>, x: usize) -> usize {
+        self.pc.count_lt(range, x)
+    }
+
+    /// Return the `k`-th smallest PC value (0-indexed) within `range`, or
+    /// `None` if the window is empty or `k` is out of range.
+    pub fn pc_quantile(&self, range: std::ops::Range<usize>, k: usize) -> Option<usize> {
+        self.pc.quantile(k, range)
+    }
+
+    /// Return the `k`-th most frequent target address within `range` as
+    /// `(target, count)` (0-indexed), or `None` if `range` holds fewer
+    /// than `k + 1` distinct targets.
+    pub fn kth_most_frequent_target(&self, range: std::ops::Range<usize>, k: usize)
+        -> Option<(usize, usize)>
+    {
+        self.tgt.topk(range, k)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(pc: usize) -> BranchRecord {
+        BranchRecord { pc, tgt: 0, flags: BranchFlags::new(BranchKind::DirectJump, Outcome::T) }
+    }
+
+    fn trace_from_records(records: &[BranchRecord]) -> BinaryTrace {
+        let data = unsafe {
+            std::slice::from_raw_parts(
+                records.as_ptr() as *const u8,
+                records.len() * std::mem::size_of::<BranchRecord>(),
+            ).to_vec()
+        };
+        BinaryTrace { data, name: "test".to_string(), num_entries: records.len() }
+    }
+
+    #[test]
+    fn pc_count_lt_and_quantile_match_a_linear_scan_over_the_window() {
+        let pcs = [30usize, 10, 20, 10, 40, 5, 25];
+        let records: Vec<BranchRecord> = pcs.iter().map(|&pc| record(pc)).collect();
+        let trace = trace_from_records(&records);
+        let index = TraceIndex::build(&trace);
+
+        let range = 1..6;
+        let window: Vec<usize> = pcs[range.clone()].to_vec();
+
+        for x in 0..=45 {
+            let expect = window.iter().filter(|&&v| v < x).count();
+            assert_eq!(index.pc_count_lt(range.clone(), x), expect, "count_lt(x={x})");
+        }
+
+        let mut sorted = window.clone();
+        sorted.sort();
+        for (k, &expect) in sorted.iter().enumerate() {
+            assert_eq!(index.pc_quantile(range.clone(), k), Some(expect));
+        }
+        assert_eq!(index.pc_quantile(range, sorted.len()), None);
+    }
+}
+
 
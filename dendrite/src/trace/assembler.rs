@@ -1,10 +1,174 @@
 
 use crate::branch::*;
+use std::io::{self, Read, Write};
 
-pub struct SyntheticTrace { 
+pub struct SyntheticTrace {
     pub data: Vec<BranchRecord>,
 }
 
+/// Magic bytes identifying a [SyntheticTrace] encoded by
+/// [SyntheticTrace::encode] - "DSTR" (Dendrite SynThetic tRace).
+const TRACE_MAGIC: [u8; 4] = *b"DSTR";
+
+/// Binary format version written by [SyntheticTrace::encode] and checked
+/// by [SyntheticTrace::decode].
+const TRACE_VERSION: u8 = 1;
+
+/// All [BranchKind] variants, used by [SyntheticTrace::decode] to
+/// validate a record's tag byte without relying on the infallible (and
+/// panicking) `From<u32> for BranchKind`.
+const BRANCH_KINDS: [BranchKind; 7] = [
+    BranchKind::DirectBranch,
+    BranchKind::DirectJump,
+    BranchKind::IndirectJump,
+    BranchKind::IndirectBranch,
+    BranchKind::DirectCall,
+    BranchKind::IndirectCall,
+    BranchKind::Return,
+];
+
+/// Errors produced by [SyntheticTrace::decode].
+#[derive(Debug)]
+pub enum TraceDecodeError {
+    /// The input ended before a complete header or record could be read.
+    Truncated,
+
+    /// The header's magic bytes didn't match [TRACE_MAGIC].
+    BadMagic([u8; 4]),
+
+    /// The header named a format version this build doesn't understand.
+    BadVersion(u8),
+
+    /// A record's tag byte didn't correspond to any [BranchKind].
+    UnknownKind(u8),
+
+    /// The underlying reader returned some other I/O error.
+    Io(io::Error),
+}
+impl std::fmt::Display for TraceDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "truncated trace (unexpected end of input)"),
+            Self::BadMagic(got) => write!(f, "bad magic {:?} (expected {:?})", got, TRACE_MAGIC),
+            Self::BadVersion(v) => write!(f, "unsupported trace version {}", v),
+            Self::UnknownKind(tag) => write!(f, "unknown BranchKind tag {}", tag),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+impl std::error::Error for TraceDecodeError {}
+impl From<io::Error> for TraceDecodeError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            Self::Truncated
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+/// Write an unsigned LEB128 varint.
+fn write_varint(w: &mut impl Write, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            w.write_all(&[byte])?;
+            return Ok(());
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Read an unsigned LEB128 varint.
+fn read_varint(r: &mut impl Read) -> Result<u64, TraceDecodeError> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        r.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+impl SyntheticTrace {
+    /// Encode this trace into the compact binary format read back by
+    /// [SyntheticTrace::decode]: a header (magic, version, base address,
+    /// record count), then per-[BranchRecord] a tag byte for [BranchKind]
+    /// followed by varint-encoded `pc`, `tgt`, and a packed outcome byte.
+    pub fn encode(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&TRACE_MAGIC)?;
+        w.write_all(&[TRACE_VERSION])?;
+
+        let base = self.data.first().map(|r| r.pc).unwrap_or(0);
+        write_varint(w, base as u64)?;
+        write_varint(w, self.data.len() as u64)?;
+
+        for record in &self.data {
+            w.write_all(&[record.kind() as u32 as u8])?;
+            write_varint(w, record.pc as u64)?;
+            write_varint(w, record.tgt as u64)?;
+            w.write_all(&[record.outcome() as u32 as u8])?;
+        }
+        Ok(())
+    }
+
+    /// Decode a trace previously written by [SyntheticTrace::encode].
+    pub fn decode(r: &mut impl Read) -> Result<Self, TraceDecodeError> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != TRACE_MAGIC {
+            return Err(TraceDecodeError::BadMagic(magic));
+        }
+
+        let mut version = [0u8; 1];
+        r.read_exact(&mut version)?;
+        if version[0] != TRACE_VERSION {
+            return Err(TraceDecodeError::BadVersion(version[0]));
+        }
+
+        let _base = read_varint(r)?;
+        let num_records = read_varint(r)? as usize;
+
+        let mut data = Vec::with_capacity(num_records);
+        for _ in 0..num_records {
+            let mut tag = [0u8; 1];
+            r.read_exact(&mut tag)?;
+            let kind = BRANCH_KINDS.into_iter()
+                .find(|k| *k as u32 == tag[0] as u32)
+                .ok_or(TraceDecodeError::UnknownKind(tag[0]))?;
+
+            let pc = read_varint(r)? as usize;
+            let tgt = read_varint(r)? as usize;
+
+            let mut outcome_byte = [0u8; 1];
+            r.read_exact(&mut outcome_byte)?;
+            let outcome = Outcome::from_bool(outcome_byte[0] != 0);
+
+            data.push(BranchRecord { pc, tgt, flags: BranchFlags::new(kind, outcome) });
+        }
+
+        Ok(Self { data })
+    }
+
+    /// Render one line per branch (`addr: KIND pc -> tgt [t|n]`) for
+    /// eyeballing a trace, reusing the same hex-address style used
+    /// elsewhere for [BranchRecord] dumps.
+    pub fn disasm(&self) -> String {
+        let mut out = String::new();
+        for record in &self.data {
+            out.push_str(&format!("{:016x}: {:14?} {:016x} -> {:016x} [{:?}]\n",
+                record.pc, record.kind(), record.pc, record.tgt, record.outcome()
+            ));
+        }
+        out
+    }
+}
+
 /// An identifier for a particular [EmitterOp].
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
@@ -63,12 +227,45 @@ impl EmitterLoc {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum BranchTarget { 
+pub enum BranchTarget {
     /// A single fixed target location
     Direct(EmitterLoc),
 
-    /// A list of target locations
-    Indirect(Vec<EmitterLoc>),
+    /// A list of target locations, selected deterministically by the
+    /// accompanying [TargetPattern].
+    Indirect(Vec<EmitterLoc>, TargetPattern),
+}
+
+/// A deterministic policy for selecting among several indirect branch
+/// targets, analogous to how [BranchPattern] selects a conditional
+/// branch's outcome - see [EmitterOp::target_loc].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetPattern {
+    /// Walk forward through the targets once (by visit count), then hold
+    /// on the last one.
+    Sequential,
+
+    /// Hold each target for `stride` consecutive visits before
+    /// advancing, cycling back to the first target after the last.
+    Periodic(usize),
+
+    /// Cycle through the targets in round-robin order.
+    RoundRobin,
+
+    /// Cycle through a fixed sequence of indices into the target list.
+    Pattern(&'static [usize]),
+}
+impl TargetPattern {
+    /// Select an index (`< len`) into the target list given the number
+    /// of times this op has been visited so far.
+    fn select(&self, ctr: usize, len: usize) -> usize {
+        match self {
+            Self::Sequential => ctr.min(len - 1),
+            Self::Periodic(stride) => (ctr / (*stride).max(1)) % len,
+            Self::RoundRobin => ctr % len,
+            Self::Pattern(p) => p[ctr % p.len()] % len,
+        }
+    }
 }
 
 
@@ -112,17 +309,17 @@ impl EmitterOp {
         }
     }
 
-    /// Return the [BranchKind] for this op. 
+    /// Return the [BranchKind] for this op.
     pub fn kind(&self) -> BranchKind {
         match self {
-            EmitterOp::Jump(BranchTarget::Direct(_)) 
+            EmitterOp::Jump(BranchTarget::Direct(_))
                 => BranchKind::DirectJump,
-            EmitterOp::Jump(BranchTarget::Indirect(_)) 
+            EmitterOp::Jump(BranchTarget::Indirect(_, _))
                 => BranchKind::IndirectJump,
-            EmitterOp::Branch(BranchTarget::Direct(_), _) 
+            EmitterOp::Branch(BranchTarget::Direct(_), _)
                 => BranchKind::DirectBranch,
-            EmitterOp::Branch(BranchTarget::Indirect(_), _) 
-                => unimplemented!(),
+            EmitterOp::Branch(BranchTarget::Indirect(_, _), _)
+                => BranchKind::IndirectBranch,
         }
     }
 
@@ -145,14 +342,14 @@ impl EmitterOp {
     }
 
     /// Generate a branch target with the provided value.
-    pub fn target_loc(&self, _ctr: usize) -> &EmitterLoc {
+    pub fn target_loc(&self, ctr: usize) -> &EmitterLoc {
         match self {
             EmitterOp::Jump(BranchTarget::Direct(loc)) |
             EmitterOp::Branch(BranchTarget::Direct(loc), _) => loc,
 
-            EmitterOp::Jump(BranchTarget::Indirect(_locs)) |
-            EmitterOp::Branch(BranchTarget::Indirect(_locs), _) => {
-                unimplemented!();
+            EmitterOp::Jump(BranchTarget::Indirect(locs, pat)) |
+            EmitterOp::Branch(BranchTarget::Indirect(locs, pat), _) => {
+                &locs[pat.select(ctr, locs.len())]
             },
         }
     }
@@ -226,6 +423,24 @@ impl TraceAssembler {
         ));
     }
 
+    /// Emit an unconditional indirect jump to one of `tgts`, selected
+    /// deterministically at compile-time according to `pattern` (e.g. a
+    /// vtable call or a computed `goto`).
+    pub fn indirect_jump_to_labels(&mut self, tgts: &[Label], pattern: TargetPattern) {
+        let locs = tgts.iter().map(|l| EmitterLoc::Label(*l)).collect();
+        self.push_op(EmitterOp::Jump(BranchTarget::Indirect(locs, pattern)));
+    }
+
+    /// Emit a conditional indirect branch (e.g. a jump-table-dispatched
+    /// multi-way switch) whose "taken" path lands on one of `tgts`,
+    /// selected deterministically according to `pattern`.
+    pub fn indirect_branch_to_labels(&mut self,
+        tgts: &[Label], pattern: TargetPattern, pat: BranchPattern
+    ) {
+        let locs = tgts.iter().map(|l| EmitterLoc::Label(*l)).collect();
+        self.push_op(EmitterOp::Branch(BranchTarget::Indirect(locs, pattern), pat));
+    }
+
     /// Increment the program counter by some value.
     pub fn pad(&mut self, len: usize) { 
         self.cursor = self.cursor + len; 
@@ -271,8 +486,8 @@ impl TraceAssembler {
 
         // Rewrite indirect targets
         let indir_locs = self.ops.iter_mut().filter_map(|op| { match op {
-            EmitterOp::Branch(BranchTarget::Indirect(ref mut locs), _) |
-            EmitterOp::Jump(BranchTarget::Indirect(ref mut locs)) => Some(locs),
+            EmitterOp::Branch(BranchTarget::Indirect(ref mut locs, _), _) |
+            EmitterOp::Jump(BranchTarget::Indirect(ref mut locs, _)) => Some(locs),
             _ => None,
         }});
 
@@ -290,6 +505,89 @@ impl TraceAssembler {
     }
 
 
+    /// The single statically-known successor of `ops[idx]`, or `None` if
+    /// its outcome depends on per-visit state (`ctr`) rather than being a
+    /// compile-time constant - see [`TraceAssembler::thread_jumps`].
+    fn const_successor(ops: &[EmitterOp], idx: usize) -> Option<usize> {
+        match &ops[idx] {
+            EmitterOp::Jump(BranchTarget::Direct(_)) => {
+                Some(ops[idx].target_loc(0).get_index())
+            },
+            EmitterOp::Branch(BranchTarget::Direct(_), BranchPattern::AlwaysTaken) |
+            EmitterOp::Branch(BranchTarget::Direct(_), BranchPattern::NeverTaken) => {
+                match ops[idx].outcome(0) {
+                    Outcome::T => Some(ops[idx].target_loc(0).get_index()),
+                    Outcome::N => Some(idx + ops[idx].size()),
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Thread control flow through runs of compile-time-constant edges
+    /// (`Jump`s and fully-biased `Branch`es) before [`TraceAssembler::compile`]
+    /// unrolls the program, so a chain of glue jumps collapses into a
+    /// single edge landing directly on the first op with data-dependent
+    /// behavior.
+    ///
+    /// This only rewrites the *target* of each constant op in place -
+    /// every op keeps its own identity and is still emitted as its own
+    /// [`BranchRecord`] whenever it's actually reached, so branches a
+    /// caller wants to observe in the trace aren't deleted. This pass is
+    /// opt-in: `compile` never threads on its own, so call this first if
+    /// you want the effect.
+    ///
+    /// A cycle of purely-constant edges (e.g. a `Jump` to itself, or an
+    /// `AlwaysTaken` loop) is left unresolved, so [`TraceAssembler::compile`]
+    /// falls back to walking it as normal.
+    pub fn thread_jumps(&mut self) {
+        self.rewrite_labels();
+
+        let num_ops = self.ops.len();
+        let mut resolved: Vec<Option<usize>> = vec![None; num_ops];
+
+        for start in 0..num_ops {
+            if resolved[start].is_some() || Self::const_successor(&self.ops, start).is_none() {
+                continue;
+            }
+
+            // Walk the chain of constant edges from `start`, tracking the
+            // indices visited on this walk to detect a cycle.
+            let mut path = Vec::new();
+            let mut cur = start;
+            let terminal = loop {
+                if cur >= num_ops || path.contains(&cur) {
+                    break if cur >= num_ops { Some(cur) } else { None };
+                }
+                path.push(cur);
+                match Self::const_successor(&self.ops, cur) {
+                    Some(next) => cur = next,
+                    None => break Some(cur),
+                }
+            };
+
+            if let Some(terminal) = terminal {
+                for &idx in &path {
+                    resolved[idx] = Some(terminal);
+                }
+            }
+        }
+
+        for (idx, target) in resolved.into_iter().enumerate() {
+            let Some(target) = target else { continue };
+            if target == idx {
+                continue;
+            }
+            match &mut self.ops[idx] {
+                EmitterOp::Jump(BranchTarget::Direct(loc)) |
+                EmitterOp::Branch(BranchTarget::Direct(loc), _) => {
+                    *loc = EmitterLoc::Index(target);
+                },
+                _ => {},
+            }
+        }
+    }
+
     /// Unroll this program into a trace.
     pub fn compile(&mut self, max_iters: usize) -> SyntheticTrace {
         self.rewrite_labels();
@@ -315,7 +613,7 @@ impl TraceAssembler {
             let kind    = op.kind();
             ctr[cur] += 1;
 
-            let record  = BranchRecord { pc, tgt, outcome, kind };
+            let record  = BranchRecord { pc, tgt, flags: BranchFlags::new(kind, outcome) };
             data.push(record);
 
             // Go to the next instruction
@@ -330,4 +628,77 @@ impl TraceAssembler {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample_trace() -> SyntheticTrace {
+        SyntheticTrace {
+            data: vec![
+                BranchRecord {
+                    pc: 0x1000, tgt: 0x2000,
+                    flags: BranchFlags::new(BranchKind::DirectJump, Outcome::T),
+                },
+                BranchRecord {
+                    pc: 0x2000, tgt: 0x2010,
+                    flags: BranchFlags::new(BranchKind::DirectBranch, Outcome::N),
+                },
+                BranchRecord {
+                    pc: 0x2010, tgt: 0,
+                    flags: BranchFlags::new(BranchKind::Return, Outcome::T),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn encode_then_decode_round_trips_every_record() {
+        let trace = sample_trace();
+        let mut buf = Vec::new();
+        trace.encode(&mut buf).unwrap();
+
+        let decoded = SyntheticTrace::decode(&mut &buf[..]).unwrap();
+        assert_eq!(decoded.data, trace.data);
+    }
+
+    #[test]
+    fn decode_rejects_bad_magic() {
+        let mut buf = Vec::new();
+        sample_trace().encode(&mut buf).unwrap();
+        buf[0] = b'X';
+
+        let err = SyntheticTrace::decode(&mut &buf[..]).unwrap_err();
+        assert!(matches!(err, TraceDecodeError::BadMagic(_)));
+    }
+
+    #[test]
+    fn decode_rejects_unsupported_version() {
+        let mut buf = Vec::new();
+        sample_trace().encode(&mut buf).unwrap();
+        buf[4] = TRACE_VERSION + 1;
+
+        let err = SyntheticTrace::decode(&mut &buf[..]).unwrap_err();
+        assert!(matches!(err, TraceDecodeError::BadVersion(v) if v == TRACE_VERSION + 1));
+    }
+
+    #[test]
+    fn decode_rejects_truncated_input() {
+        let mut buf = Vec::new();
+        sample_trace().encode(&mut buf).unwrap();
+        buf.truncate(buf.len() - 1);
+
+        let err = SyntheticTrace::decode(&mut &buf[..]).unwrap_err();
+        assert!(matches!(err, TraceDecodeError::Truncated));
+    }
+
+    #[test]
+    fn disasm_renders_one_line_per_record() {
+        let trace = sample_trace();
+        let out = trace.disasm();
+        assert_eq!(out.lines().count(), trace.data.len());
+        assert!(out.contains("DirectJump"));
+        assert!(out.contains("[t]"));
+    }
+}
+
 
@@ -0,0 +1,251 @@
+
+//! A portable, versioned trace format for capturing and replaying
+//! [BranchRecord] streams, independent of [BranchRecord]'s in-memory layout.
+//!
+//! Unlike [super::BinaryTrace]/[super::BinaryTraceReader], which round-trip
+//! [BranchRecord] by transmuting its raw (platform- and build-dependent)
+//! `repr(C)` bytes, [TraceWriter]/[TraceReader] serialize each field
+//! explicitly in a recorded byte order behind a versioned header, so a
+//! trace captured on one machine/build stays interpretable on another even
+//! as [BranchFlags]'s bit layout evolves. This is the format meant for
+//! `--trace_branches`-style capture/replay: write once during a real run,
+//! then read back later against any number of predictor configurations.
+
+use std::io::{self, Read, Write};
+use crate::branch::*;
+
+/// Magic bytes identifying a trace written by [TraceWriter] - "DTRC"
+/// (Dendrite TRaCe).
+const TRACE_MAGIC: [u8; 4] = *b"DTRC";
+
+/// Binary format version written by [TraceWriter] and checked by
+/// [TraceReader::new].
+const TRACE_VERSION: u8 = 1;
+
+/// Version of [BranchFlags]'s bit layout assumed by this build. Bump this
+/// whenever a flag bit is added, removed, or moved, so [TraceReader] can
+/// refuse to reinterpret a trace captured against a different layout
+/// instead of silently misreading its flags.
+const FLAGS_LAYOUT_VERSION: u8 = 1;
+
+/// Byte order a trace was written in, recorded in its header so a
+/// [TraceReader] can interpret fields captured on a foreign-endian host
+/// instead of silently misreading them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+impl Endianness {
+    /// The endianness of the machine this build is running on.
+    fn native() -> Self {
+        if cfg!(target_endian = "big") { Self::Big } else { Self::Little }
+    }
+    fn to_byte(self) -> u8 {
+        match self { Self::Little => 0, Self::Big => 1 }
+    }
+    fn from_byte(b: u8) -> Result<Self, TraceFormatError> {
+        match b {
+            0 => Ok(Self::Little),
+            1 => Ok(Self::Big),
+            other => Err(TraceFormatError::BadEndianness(other)),
+        }
+    }
+}
+
+/// Errors produced by [TraceReader::new] / [TraceReader::next].
+#[derive(Debug)]
+pub enum TraceFormatError {
+    /// The input ended before a complete header or record could be read.
+    Truncated,
+
+    /// The header's magic bytes didn't match [TRACE_MAGIC].
+    BadMagic([u8; 4]),
+
+    /// The header named a format version this build doesn't understand.
+    BadVersion(u8),
+
+    /// The header named an endianness byte that isn't 0 or 1.
+    BadEndianness(u8),
+
+    /// The header's `BranchFlags` layout version doesn't match
+    /// [FLAGS_LAYOUT_VERSION] - the trace was captured against a build
+    /// with a different flag-bit layout and can't be safely reinterpreted.
+    FlagsLayoutMismatch(u8),
+
+    /// A decoded record's flags failed [`BranchFlags::validate`] - the
+    /// trace is corrupt rather than merely written by an older build.
+    InvalidFlags(BranchFlagsError),
+
+    /// The underlying reader returned some other I/O error.
+    Io(io::Error),
+}
+impl std::fmt::Display for TraceFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "truncated trace (unexpected end of input)"),
+            Self::BadMagic(got) => write!(f, "bad magic {:?} (expected {:?})", got, TRACE_MAGIC),
+            Self::BadVersion(v) => write!(f, "unsupported trace version {}", v),
+            Self::BadEndianness(b) => write!(f, "unrecognized endianness byte {}", b),
+            Self::FlagsLayoutMismatch(v) => write!(f,
+                "trace was captured with BranchFlags layout version {} (this build expects {})",
+                v, FLAGS_LAYOUT_VERSION
+            ),
+            Self::InvalidFlags(e) => write!(f, "invalid record flags: {}", e),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+impl std::error::Error for TraceFormatError {}
+impl From<io::Error> for TraceFormatError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            Self::Truncated
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+fn write_u64(w: &mut impl Write, val: u64, end: Endianness) -> io::Result<()> {
+    let bytes = match end {
+        Endianness::Little => val.to_le_bytes(),
+        Endianness::Big => val.to_be_bytes(),
+    };
+    w.write_all(&bytes)
+}
+fn read_u64(r: &mut impl Read, end: Endianness) -> Result<u64, TraceFormatError> {
+    let mut bytes = [0u8; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(match end {
+        Endianness::Little => u64::from_le_bytes(bytes),
+        Endianness::Big => u64::from_be_bytes(bytes),
+    })
+}
+fn write_u32(w: &mut impl Write, val: u32, end: Endianness) -> io::Result<()> {
+    let bytes = match end {
+        Endianness::Little => val.to_le_bytes(),
+        Endianness::Big => val.to_be_bytes(),
+    };
+    w.write_all(&bytes)
+}
+fn read_u32(r: &mut impl Read, end: Endianness) -> Result<u32, TraceFormatError> {
+    let mut bytes = [0u8; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(match end {
+        Endianness::Little => u32::from_le_bytes(bytes),
+        Endianness::Big => u32::from_be_bytes(bytes),
+    })
+}
+
+/// Streams [BranchRecord]s out to a compact, versioned binary format that
+/// stays interpretable across machines and builds - see the module docs.
+///
+/// Each record is written as a fixed-width `pc` (8 bytes), `tgt` (8
+/// bytes), `flags.0` (4 bytes) triple in the header's recorded byte order,
+/// regardless of the host's native `usize` width.
+pub struct TraceWriter<W: Write> {
+    inner: W,
+    endianness: Endianness,
+}
+impl<W: Write> TraceWriter<W> {
+    /// Create a writer and immediately emit the header: magic, version,
+    /// endianness, and the [BranchFlags] layout version.
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        let endianness = Endianness::native();
+        inner.write_all(&TRACE_MAGIC)?;
+        inner.write_all(&[TRACE_VERSION, endianness.to_byte(), FLAGS_LAYOUT_VERSION])?;
+        Ok(Self { inner, endianness })
+    }
+
+    /// Append a single record.
+    pub fn write_record(&mut self, record: &BranchRecord) -> io::Result<()> {
+        write_u64(&mut self.inner, record.pc as u64, self.endianness)?;
+        write_u64(&mut self.inner, record.tgt as u64, self.endianness)?;
+        write_u32(&mut self.inner, record.flags.bits(), self.endianness)?;
+        Ok(())
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads back a trace written by [TraceWriter], yielding one [BranchRecord]
+/// at a time instead of holding the whole trace in memory - suitable for
+/// traces much larger than RAM, like [super::BinaryTraceReader].
+pub struct TraceReader<R: Read> {
+    inner: R,
+    endianness: Endianness,
+}
+impl<R: Read> TraceReader<R> {
+    /// Read and validate the header, then return a reader positioned at
+    /// the first record.
+    pub fn new(mut inner: R) -> Result<Self, TraceFormatError> {
+        let mut magic = [0u8; 4];
+        inner.read_exact(&mut magic)?;
+        if magic != TRACE_MAGIC {
+            return Err(TraceFormatError::BadMagic(magic));
+        }
+
+        let mut rest = [0u8; 3];
+        inner.read_exact(&mut rest)?;
+        let [version, endianness_byte, flags_layout_version] = rest;
+
+        if version != TRACE_VERSION {
+            return Err(TraceFormatError::BadVersion(version));
+        }
+        let endianness = Endianness::from_byte(endianness_byte)?;
+        if flags_layout_version != FLAGS_LAYOUT_VERSION {
+            return Err(TraceFormatError::FlagsLayoutMismatch(flags_layout_version));
+        }
+
+        Ok(Self { inner, endianness })
+    }
+}
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = Result<BranchRecord, TraceFormatError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        // Peek a single byte first so a clean EOF at a record boundary
+        // ends iteration instead of being reported as an error.
+        let mut pc_bytes = [0u8; 8];
+        match self.inner.read(&mut pc_bytes[..1]) {
+            Ok(0) => return None,
+            Ok(_) => {},
+            Err(e) => return Some(Err(e.into())),
+        }
+
+        let record = (|| {
+            self.inner.read_exact(&mut pc_bytes[1..])?;
+            let pc = match self.endianness {
+                Endianness::Little => u64::from_le_bytes(pc_bytes),
+                Endianness::Big => u64::from_be_bytes(pc_bytes),
+            };
+            let tgt = read_u64(&mut self.inner, self.endianness)?;
+            let flags = BranchFlags::from_bits_retain(read_u32(&mut self.inner, self.endianness)?);
+            flags.validate().map_err(TraceFormatError::InvalidFlags)?;
+            Ok(BranchRecord { pc: pc as usize, tgt: tgt as usize, flags })
+        })();
+
+        Some(record)
+    }
+}
+
+/// Format a single record in the human-readable `PC -> T target` (taken)
+/// or `PC -> N fallthrough` (not taken) line format, for eyeballing a
+/// trace or diffing two runs by hand.
+pub fn text_line(record: &BranchRecord) -> String {
+    match record.outcome() {
+        Outcome::T => format!("{:016x} -> T {:016x}", record.pc, record.tgt),
+        Outcome::N => format!("{:016x} -> N {:016x}", record.pc, record.pc + record.ilen()),
+    }
+}
+
+/// Write an entire trace in the [text_line] format, one record per line.
+pub fn write_text(w: &mut impl Write, records: &[BranchRecord]) -> io::Result<()> {
+    for record in records {
+        writeln!(w, "{}", text_line(record))?;
+    }
+    Ok(())
+}
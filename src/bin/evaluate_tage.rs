@@ -70,7 +70,7 @@ fn build_tage() -> TAGEPredictor {
                 default_state: Outcome::N,
             },
             size: 1 << 12,
-            index_strat: IndexStrategy::FromPc(tage_base_fold_pc_12b),
+            index_strat: IndexStrategy::FromPc("fold_pc_12b", tage_base_fold_pc_12b),
         },
     );
 
@@ -86,8 +86,8 @@ fn build_tage() -> TAGEPredictor {
                     max_n_state: 1,
                     default_state: Outcome::N,
                 },
-                index_strat: IndexStrategy::FromPhr(tage_fold_phr_ghist_12b),
-                tag_strat: TagStrategy::FromPc(tage_compute_tag),
+                index_strat: IndexStrategy::FromPcAndPath("fold_phr_ghist_12b", tage_fold_phr_ghist_12b),
+                tag_strat: TagStrategy::FromPc("compute_tag_12b", tage_compute_tag),
         });
     }
 
@@ -166,9 +166,10 @@ fn main() {
                 let stat = stats.get_mut(record.pc);
                 stat.pat.push(record.outcome.into());
 
-                let inputs = TAGEInputs { 
+                let inputs = TAGEInputs {
                     pc: record.pc,
                     phr: &phr,
+                    ghr: &ghr,
                 };
                 let p = tage.predict(inputs.clone());
                 if record.outcome == p.outcome {
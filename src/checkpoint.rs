@@ -0,0 +1,64 @@
+//! Serde-based checkpoint/restore support, used by [crate::predictor::tage]
+//! (and anything shaped like a table of [crate::predictor::SaturatingCounter])
+//! to snapshot a fully-warmed predictor to a compact binary encoding (CBOR)
+//! and resume simulation later without replaying the warm-up trace.
+
+use std::fs::File;
+use std::io::{ self, BufReader, BufWriter };
+use std::path::Path;
+
+use serde::{ Serialize, de::DeserializeOwned };
+
+/// An error encountered while saving or loading a checkpoint.
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// Failed to open, read, or write the checkpoint file.
+    Io(io::Error),
+
+    /// Failed to encode a checkpoint as CBOR.
+    Encode(ciborium::ser::Error<io::Error>),
+
+    /// Failed to decode a checkpoint from CBOR.
+    Decode(ciborium::de::Error<io::Error>),
+
+    /// A checkpoint was taken with an [IndexStrategy]/[TagStrategy] whose
+    /// stable identifier doesn't match the one the caller re-registered
+    /// at load time - the checkpoint and the running binary disagree
+    /// about which `fn` a table was built with.
+    ///
+    /// [IndexStrategy]: crate::predictor::IndexStrategy
+    /// [TagStrategy]: crate::predictor::TagStrategy
+    StrategyMismatch { expected: String, found: String },
+}
+impl std::fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "checkpoint I/O error: {}", e),
+            Self::Encode(e) => write!(f, "checkpoint encode error: {}", e),
+            Self::Decode(e) => write!(f, "checkpoint decode error: {}", e),
+            Self::StrategyMismatch { expected, found } => write!(f,
+                "checkpoint strategy mismatch: checkpoint was taken with \
+                 {:?}, but {:?} was registered at load time", expected, found),
+        }
+    }
+}
+impl std::error::Error for CheckpointError {}
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self { Self::Io(e) }
+}
+
+/// Write `value` to `path` as CBOR.
+pub fn save_cbor<T: Serialize>(value: &T, path: impl AsRef<Path>)
+    -> Result<(), CheckpointError>
+{
+    let file = BufWriter::new(File::create(path)?);
+    ciborium::into_writer(value, file).map_err(CheckpointError::Encode)
+}
+
+/// Read a value of type `T` previously written with [save_cbor].
+pub fn load_cbor<T: DeserializeOwned>(path: impl AsRef<Path>)
+    -> Result<T, CheckpointError>
+{
+    let file = BufReader::new(File::open(path)?);
+    ciborium::from_reader(file).map_err(CheckpointError::Decode)
+}
@@ -35,6 +35,25 @@ impl GlobalHistoryRegister {
     pub fn len(&self) -> usize { self.len }
     pub fn data(&self) -> &BitVec { &self.data }
     pub fn data_mut(&mut self) -> &mut BitVec { &mut self.data }
+
+    /// Capture the current register state for later [Self::restore].
+    pub fn checkpoint(&self) -> GlobalHistorySnapshot {
+        GlobalHistorySnapshot { data: self.data.clone() }
+    }
+
+    /// Restore register state previously captured with [Self::checkpoint],
+    /// e.g. to roll speculative history back to a known-good point after
+    /// a misprediction.
+    pub fn restore(&mut self, snapshot: &GlobalHistorySnapshot) {
+        self.data = snapshot.data.clone();
+    }
+}
+
+/// A saved [GlobalHistoryRegister] state, as produced by
+/// [GlobalHistoryRegister::checkpoint].
+#[derive(Clone, Debug)]
+pub struct GlobalHistorySnapshot {
+    data: BitVec<usize, Lsb0>,
 }
 
 
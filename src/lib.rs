@@ -6,14 +6,30 @@
 pub mod history;
 pub mod predictor;
 pub mod trace;
+pub mod direction;
+pub mod sim;
+pub mod checkpoint;
+
+/// The full L-TAGE/TAGE-SC-L predictor (loop predictor + statistical
+/// corrector on top of TAGE) - kept as its own top-level module, rather
+/// than glob-exported like the other modules above, since its
+/// `TAGEPredictor`/`TAGEComponent`/`TAGEEntry`/etc. names collide with
+/// [`predictor::tage`]'s own (simpler, no loop/SC) TAGE implementation.
+/// Reach its types through `ltage::` explicitly.
+#[path = "tage.rs"]
+pub mod ltage;
 
 pub use trace::*;
 pub use history::*;
 pub use predictor::*;
+pub use sim::*;
+pub use checkpoint::*;
+
+use serde::{ Serialize, Deserialize };
 
-/// A branch outcome. 
+/// A branch outcome.
 #[repr(u32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Outcome { N = 0, T = 1 }
 impl std::ops::Not for Outcome { 
     type Output = Self;
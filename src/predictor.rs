@@ -14,37 +14,92 @@ use crate::history::*;
 use crate::Outcome;
 
 
-/// Some hash function used to create an index from a program counter value. 
+/// Some hash function used to create an index from a program counter value.
 pub type PcIndexFn<T> = fn(&T, pc: usize) -> usize;
 
-/// Some hash function used to create an index from a program counter value 
-/// and a reference to some [HistoryRegister] used for path history. 
-pub type PhrIndexFn<T> = 
+/// Some hash function used to create an index from a program counter value
+/// and a reference to some [HistoryRegister] used for path history (the
+/// sequence of recently-taken branch PCs, as opposed to [GlobalHistoryRegister]
+/// direction bits).
+pub type PathIndexFn<T> =
     fn(&T, pc: usize, phr: &HistoryRegister) -> usize;
 
-/// A user-provided strategy for indexing into some object implementing 
-/// [PredictorTable].
-///
+/// Some hash function used to create an index from a program counter value
+/// and a reference to the raw (un-folded) [HistoryRegister] direction
+/// history, for tables that want to hash global history bits directly
+/// rather than through a component's own folded-history CSR.
+pub type HistoryIndexFn<T> =
+    fn(&T, pc: usize, ghr: &HistoryRegister) -> usize;
+
+/// A stable name for an [IndexStrategy] or [TagStrategy], recorded in a
+/// checkpoint in place of the `fn` pointer itself (which can't be
+/// serialized). Restoring a checkpoint requires the caller to re-register
+/// a strategy under the same id; see [crate::checkpoint].
+pub type StrategyId = &'static str;
+
+/// A user-provided strategy for indexing into some object implementing
+/// [PredictorTable], tagged with a [StrategyId] so the choice of strategy
+/// can survive a checkpoint round-trip even though the `fn` pointer can't.
 #[derive(Clone, Copy, Debug)]
 pub enum IndexStrategy<T> {
-    FromPc(PcIndexFn<T>),
-    FromPhr(PhrIndexFn<T>),
+    FromPc(StrategyId, PcIndexFn<T>),
+    FromPcAndPath(StrategyId, PathIndexFn<T>),
+    FromPcAndHistory(StrategyId, HistoryIndexFn<T>),
+}
+impl<T> IndexStrategy<T> {
+    /// The stable identifier recorded for this strategy.
+    pub fn id(&self) -> StrategyId {
+        match self {
+            Self::FromPc(id, _) => id,
+            Self::FromPcAndPath(id, _) => id,
+            Self::FromPcAndHistory(id, _) => id,
+        }
+    }
 }
 
 
-/// A user-provided strategy for generating a tag associated with some 
-/// entry in an object implementing [PredictorTable].
+/// A user-provided strategy for generating a tag associated with some
+/// entry in an object implementing [PredictorTable], tagged with a
+/// [StrategyId] so the choice of strategy can survive a checkpoint
+/// round-trip even though the `fn` pointer can't.
 #[derive(Clone, Copy, Debug)]
 pub enum TagStrategy<T> {
-    FromPc(PcIndexFn<T>),
+    FromPc(StrategyId, PcIndexFn<T>),
+    FromPcAndPath(StrategyId, PathIndexFn<T>),
+    FromPcAndHistory(StrategyId, HistoryIndexFn<T>),
+}
+impl<T> TagStrategy<T> {
+    /// The stable identifier recorded for this strategy.
+    pub fn id(&self) -> StrategyId {
+        match self {
+            Self::FromPc(id, _) => id,
+            Self::FromPcAndPath(id, _) => id,
+            Self::FromPcAndHistory(id, _) => id,
+        }
+    }
 }
 
-/// Interface to a "trivial" predictor that simply guesses an outcome. 
+/// Interface to a "trivial" predictor that simply guesses an outcome.
 pub trait SimplePredictor {
     fn name(&self) -> &'static str;
     fn predict(&self) -> Outcome;
 }
 
+/// Interface to a predictor with some internal state which is only subject
+/// to change by the correct branch outcome.
+pub trait StatefulPredictor {
+    fn name(&self) -> &'static str;
+
+    /// Reset the internal state of the predictor.
+    fn reset(&mut self);
+
+    /// Return the current predicted outcome.
+    fn predict(&self) -> Outcome;
+
+    /// Update the internal state of the predictor with the correct outcome.
+    fn update(&mut self, outcome: Outcome);
+}
+
 
 /// Interface to a table of predictors. 
 pub trait PredictorTable { 
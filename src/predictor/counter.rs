@@ -1,7 +1,9 @@
 
 use crate::Outcome;
+use crate::predictor::StatefulPredictor;
+use serde::{ Serialize, Deserialize };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SaturatingCounterConfig {
     pub max_t_state: u8,
     pub max_n_state: u8,
@@ -21,8 +23,8 @@ impl SaturatingCounterConfig {
     }
 }
 
-/// An 'n'-bit saturating counter used to follow the behavior of a branch. 
-#[derive(Clone, Copy, Debug)]
+/// An 'n'-bit saturating counter used to follow the behavior of a branch.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SaturatingCounter {
     cfg: SaturatingCounterConfig,
     state: Outcome,
@@ -50,24 +52,36 @@ impl SaturatingCounter {
     }
 
     /// Reset the counter.
-    pub fn reset(&mut self) { 
-        self.state = self.cfg.default_state; 
+    pub fn reset(&mut self) {
+        self.state = self.cfg.default_state;
         self.ctr = 0;
     }
 
     /// Return the current predicted direction.
     pub fn predict(&self) -> Outcome { self.state }
 
-    /// Update the state of the counter. 
+    /// Return the counter's internal confidence in the current direction,
+    /// where `0` is the weakest (having just crossed over from the
+    /// opposite direction).
+    pub fn magnitude(&self) -> u8 { self.ctr }
+
+    /// Update the state of the counter.
     pub fn update(&mut self, outcome: Outcome) {
         let prediction = self.predict();
         if outcome != prediction {
             self.weaken();
-        } else { 
+        } else {
             self.strengthen();
         }
     }
 }
 
+impl StatefulPredictor for SaturatingCounter {
+    fn name(&self) -> &'static str { "SaturatingCounter" }
+    fn predict(&self) -> Outcome { self.predict() }
+    fn reset(&mut self) { self.reset() }
+    fn update(&mut self, outcome: Outcome) { self.update(outcome) }
+}
+
 
 
@@ -4,19 +4,31 @@ pub use component::*;
 
 use bitvec::prelude::*;
 use rand::distributions::{ WeightedIndex, Distribution };
+use serde::{ Serialize, Deserialize };
+use std::path::Path;
 
 use crate::history::*;
 use crate::Outcome;
 use crate::predictor::*;
+use crate::checkpoint::*;
 
 /// Container for inputs passed to TAGE components.
 #[derive(Clone)]
-pub struct TAGEInputs<'a> { 
+pub struct TAGEInputs<'a> {
     /// Program counter associated with a predicted branch
     pub pc: usize,
 
-    /// Bits from a path history register
+    /// Bits from a path history register (sequence of recently-taken
+    /// branch PCs), threaded alongside `ghr` for tables whose
+    /// [IndexStrategy]/[TagStrategy] is [IndexStrategy::FromPcAndPath].
     pub phr: &'a HistoryRegister,
+
+    /// Raw (un-folded) global history bits, threaded alongside `phr` for
+    /// tables whose [IndexStrategy]/[TagStrategy] is
+    /// [IndexStrategy::FromPcAndHistory] - as opposed to a component's
+    /// own folded-history CSR (`csr`), which every tagged component
+    /// maintains incrementally regardless of which strategy it uses.
+    pub ghr: &'a HistoryRegister,
 }
 
 
@@ -100,10 +112,45 @@ impl TAGEConfig {
             .collect::<Vec<TAGEComponent>>();
         let base = self.base.build();
         let stat = TAGEStats::new(comp.len());
-        TAGEPredictor { cfg, base, comp, stat, 
+        TAGEPredictor { cfg, base, comp, stat,
             reset_ctr: 0,
         }
     }
+
+    /// Capture the serializable parts of this config; see
+    /// [TAGEBaseConfig::to_snapshot] and
+    /// [TAGEComponentConfig::to_snapshot].
+    pub fn to_snapshot(&self) -> TAGEConfigSnapshot {
+        TAGEConfigSnapshot {
+            base: self.base.to_snapshot(),
+            comp: self.comp.iter().map(|c| c.to_snapshot()).collect(),
+        }
+    }
+}
+
+/// The serializable parts of a [TAGEConfig], as produced by
+/// [TAGEConfig::to_snapshot].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TAGEConfigSnapshot {
+    pub base: TAGEBaseConfigSnapshot,
+    pub comp: Vec<TAGEComponentConfigSnapshot>,
+}
+impl TAGEConfigSnapshot {
+    /// Re-attach caller-provided strategies - one `(index_strat,
+    /// tag_strat)` pair per tagged component, in the same order as
+    /// [TAGEConfig::comp] - erroring if any [StrategyId] doesn't match
+    /// the one this snapshot was taken with.
+    pub fn into_config(self,
+        base_index_strat: IndexStrategy<TAGEBaseComponent>,
+        comp_strats: Vec<(IndexStrategy<TAGEComponent>, TagStrategy<TAGEComponent>)>,
+    ) -> Result<TAGEConfig, CheckpointError>
+    {
+        let base = self.base.into_config(base_index_strat)?;
+        let comp = self.comp.into_iter().zip(comp_strats)
+            .map(|(snap, (index_strat, tag_strat))| snap.into_config(index_strat, tag_strat))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TAGEConfig { base, comp })
+    }
 }
 
 #[derive(Debug)]
@@ -128,12 +175,20 @@ impl TAGEStats {
 
 
 
-/// The "TAgged GEometric history length" predictor. 
+/// The "TAgged GEometric history length" predictor.
 ///
-/// See the following: 
-///  - "A case for (partially) TAgged GEometric history length branch prediction" 
+/// See the following:
+///  - "A case for (partially) TAgged GEometric history length branch prediction"
 ///  (Seznec, 2006).
-pub struct TAGEPredictor { 
+///
+/// Unlike [SaturatingCounter], which models the state of a single slot and
+/// so implements the zero-argument [StatefulPredictor], a TAGE predictor
+/// has to hash the program counter (and folded history) against several
+/// tagged tables simultaneously to find a provider - there's no single
+/// piece of "the" internal state to expose without a PC, so this keeps its
+/// own `predict`/`update` taking [TAGEInputs] explicitly rather than
+/// implementing [StatefulPredictor].
+pub struct TAGEPredictor {
     pub cfg: TAGEConfig,
 
     pub stat: TAGEStats,
@@ -379,12 +434,66 @@ impl TAGEPredictor {
     }
 
     /// Given some reference to a [HistoryRegister], update the state
-    /// of the folded history register in each tagged component. 
+    /// of the folded history register in each tagged component.
     pub fn update_history(&mut self, ghr: &HistoryRegister) {
         for comp in self.comp.iter_mut() {
             comp.csr.update(ghr);
         }
     }
 
+    /// Save this predictor's config, counters, tags, and useful bits to
+    /// `path` as CBOR, so a fully-warmed predictor can be resumed later
+    /// without replaying the warm-up trace.
+    ///
+    /// This does *not* capture each component's folded-history CSR (see
+    /// [TAGEComponent::save_checkpoint]) or the [HistoryRegister] path
+    /// history threaded through [TAGEInputs] - neither is `Serialize`
+    /// yet. A restored predictor reproduces bit-identical counter/tag/
+    /// useful-bit state, but the caller must re-warm history (via
+    /// [Self::update_history] and a rebuilt path-history register)
+    /// before its *predictions* are bit-identical to the state at
+    /// checkpoint time.
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>)
+        -> Result<(), CheckpointError>
+    {
+        let snapshot = TAGEPredictorSnapshot {
+            cfg: self.cfg.to_snapshot(),
+            base: self.base.get_entries().to_vec(),
+            comp: self.comp.iter().map(|c| c.data.clone()).collect(),
+            reset_ctr: self.reset_ctr,
+        };
+        save_cbor(&snapshot, path)
+    }
+
+    /// Restore a predictor previously saved with [Self::save_checkpoint].
+    /// `base_index_strat`/`comp_strats` must be registered under the
+    /// same [StrategyId]s the checkpoint was taken with; see
+    /// [TAGEConfigSnapshot::into_config]. Every component's
+    /// folded-history CSR comes back zeroed - see [Self::save_checkpoint].
+    pub fn load_checkpoint(path: impl AsRef<Path>,
+        base_index_strat: IndexStrategy<TAGEBaseComponent>,
+        comp_strats: Vec<(IndexStrategy<TAGEComponent>, TagStrategy<TAGEComponent>)>,
+    ) -> Result<Self, CheckpointError>
+    {
+        let snapshot: TAGEPredictorSnapshot = load_cbor(path)?;
+        let cfg = snapshot.cfg.into_config(base_index_strat, comp_strats)?;
+        let mut predictor = cfg.clone().build();
+        predictor.base.set_entries(snapshot.base);
+        for (comp, data) in predictor.comp.iter_mut().zip(snapshot.comp) {
+            comp.data = data;
+        }
+        predictor.reset_ctr = snapshot.reset_ctr;
+        Ok(predictor)
+    }
+}
+
+/// A saved [TAGEPredictor], as produced by
+/// [TAGEPredictor::save_checkpoint].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TAGEPredictorSnapshot {
+    cfg: TAGEConfigSnapshot,
+    base: Vec<SaturatingCounter>,
+    comp: Vec<Vec<TAGEEntry>>,
+    reset_ctr: u8,
 }
 
@@ -2,7 +2,10 @@
 use crate::Outcome;
 use crate::history::*;
 use crate::predictor::*;
+use crate::checkpoint::*;
 use std::ops::RangeInclusive;
+use std::path::Path;
+use serde::{ Serialize, Deserialize };
 
 #[derive(Clone, Debug)]
 pub struct TAGEBaseConfig {
@@ -16,7 +19,7 @@ pub struct TAGEBaseConfig {
     pub index_strat: IndexStrategy<TAGEBaseComponent>,
 }
 impl TAGEBaseConfig {
-    pub fn storage_bits(&self) -> usize { 
+    pub fn storage_bits(&self) -> usize {
         self.ctr.storage_bits() * self.size
     }
 
@@ -27,6 +30,43 @@ impl TAGEBaseConfig {
             cfg: self,
         }
     }
+
+    /// Capture the serializable parts of this config. The `index_strat`
+    /// `fn` pointer is recorded only as its [StrategyId]; restoring it
+    /// requires the caller to re-register a matching strategy via
+    /// [TAGEBaseConfigSnapshot::into_config].
+    pub fn to_snapshot(&self) -> TAGEBaseConfigSnapshot {
+        TAGEBaseConfigSnapshot {
+            ctr: self.ctr,
+            size: self.size,
+            index_strategy_id: self.index_strat.id().to_string(),
+        }
+    }
+}
+
+/// The serializable parts of a [TAGEBaseConfig], as produced by
+/// [TAGEBaseConfig::to_snapshot].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TAGEBaseConfigSnapshot {
+    pub ctr: SaturatingCounterConfig,
+    pub size: usize,
+    pub index_strategy_id: String,
+}
+impl TAGEBaseConfigSnapshot {
+    /// Re-attach a caller-provided `index_strat`, erroring if it wasn't
+    /// registered under the same [StrategyId] this snapshot was taken
+    /// with.
+    pub fn into_config(self, index_strat: IndexStrategy<TAGEBaseComponent>)
+        -> Result<TAGEBaseConfig, CheckpointError>
+    {
+        if index_strat.id() != self.index_strategy_id {
+            return Err(CheckpointError::StrategyMismatch {
+                expected: self.index_strategy_id,
+                found: index_strat.id().to_string(),
+            });
+        }
+        Ok(TAGEBaseConfig { ctr: self.ctr, size: self.size, index_strat })
+    }
 }
 
 
@@ -39,9 +79,52 @@ pub struct TAGEBaseComponent {
     data: Vec<SaturatingCounter>,
 }
 impl TAGEBaseComponent {
-    pub fn index_mask(&self) -> usize { 
+    pub fn index_mask(&self) -> usize {
         self.cfg.size - 1
     }
+
+    /// Return the underlying table of counters.
+    pub fn get_entries(&self) -> &[SaturatingCounter] {
+        &self.data
+    }
+
+    /// Replace the underlying table of counters wholesale, e.g. when
+    /// restoring a checkpoint.
+    pub fn set_entries(&mut self, data: Vec<SaturatingCounter>) {
+        self.data = data;
+    }
+
+    /// Save this table's counters to `path` as CBOR, alongside a
+    /// [StrategyId] recording which `index_strat` it was built with.
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>)
+        -> Result<(), CheckpointError>
+    {
+        let snapshot = TAGEBaseComponentSnapshot {
+            cfg: self.cfg.to_snapshot(),
+            data: self.data.clone(),
+        };
+        save_cbor(&snapshot, path)
+    }
+
+    /// Restore a table previously saved with [Self::save_checkpoint].
+    /// `index_strat` must be registered under the same [StrategyId] the
+    /// checkpoint was taken with.
+    pub fn load_checkpoint(path: impl AsRef<Path>,
+        index_strat: IndexStrategy<TAGEBaseComponent>)
+        -> Result<Self, CheckpointError>
+    {
+        let snapshot: TAGEBaseComponentSnapshot = load_cbor(path)?;
+        let cfg = snapshot.cfg.into_config(index_strat)?;
+        Ok(Self { cfg, data: snapshot.data })
+    }
+}
+
+/// A saved [TAGEBaseComponent], as produced by
+/// [TAGEBaseComponent::save_checkpoint].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TAGEBaseComponentSnapshot {
+    cfg: TAGEBaseConfigSnapshot,
+    data: Vec<SaturatingCounter>,
 }
 impl PredictorTable for TAGEBaseComponent {
     type Input<'a> = TAGEInputs<'a>;
@@ -52,12 +135,15 @@ impl PredictorTable for TAGEBaseComponent {
 
     fn get_index(&self, input: TAGEInputs) -> usize { 
         let res = match self.cfg.index_strat {
-            IndexStrategy::FromPc(func) => { 
+            IndexStrategy::FromPc(_, func) => {
                 (func)(self, input.pc)
             },
-            IndexStrategy::FromPhr(func) => { 
+            IndexStrategy::FromPcAndPath(_, func) => {
                 (func)(self, input.pc, input.phr)
             },
+            IndexStrategy::FromPcAndHistory(_, func) => {
+                (func)(self, input.pc, input.ghr)
+            },
         };
         res & self.index_mask()
     }
@@ -73,8 +159,8 @@ impl PredictorTable for TAGEBaseComponent {
 }
 
 
-/// An entry in some [TAGEComponent]. 
-#[derive(Clone, Debug)]
+/// An entry in some [TAGEComponent].
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TAGEEntry {
     pub ctr: SaturatingCounter,
     pub useful_bits: usize,
@@ -173,6 +259,68 @@ impl TAGEComponentConfig {
             csr,
         }
     }
+
+    /// Capture the serializable parts of this config. The `index_strat`/
+    /// `tag_strat` `fn` pointers are recorded only as their
+    /// [StrategyId]s; restoring them requires the caller to re-register
+    /// matching strategies via
+    /// [TAGEComponentConfigSnapshot::into_config].
+    pub fn to_snapshot(&self) -> TAGEComponentConfigSnapshot {
+        TAGEComponentConfigSnapshot {
+            size: self.size,
+            ghr_range: (*self.ghr_range.start(), *self.ghr_range.end()),
+            tag_bits: self.tag_bits,
+            useful_bits: self.useful_bits,
+            ctr: self.ctr,
+            index_strategy_id: self.index_strat.id().to_string(),
+            tag_strategy_id: self.tag_strat.id().to_string(),
+        }
+    }
+}
+
+/// The serializable parts of a [TAGEComponentConfig], as produced by
+/// [TAGEComponentConfig::to_snapshot].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TAGEComponentConfigSnapshot {
+    pub size: usize,
+    pub ghr_range: (usize, usize),
+    pub tag_bits: usize,
+    pub useful_bits: usize,
+    pub ctr: SaturatingCounterConfig,
+    pub index_strategy_id: String,
+    pub tag_strategy_id: String,
+}
+impl TAGEComponentConfigSnapshot {
+    /// Re-attach caller-provided strategies, erroring if either wasn't
+    /// registered under the same [StrategyId]s this snapshot was taken
+    /// with.
+    pub fn into_config(self,
+        index_strat: IndexStrategy<TAGEComponent>,
+        tag_strat: TagStrategy<TAGEComponent>,
+    ) -> Result<TAGEComponentConfig, CheckpointError>
+    {
+        if index_strat.id() != self.index_strategy_id {
+            return Err(CheckpointError::StrategyMismatch {
+                expected: self.index_strategy_id,
+                found: index_strat.id().to_string(),
+            });
+        }
+        if tag_strat.id() != self.tag_strategy_id {
+            return Err(CheckpointError::StrategyMismatch {
+                expected: self.tag_strategy_id,
+                found: tag_strat.id().to_string(),
+            });
+        }
+        Ok(TAGEComponentConfig {
+            size: self.size,
+            ghr_range: self.ghr_range.0..=self.ghr_range.1,
+            tag_bits: self.tag_bits,
+            useful_bits: self.useful_bits,
+            index_strat,
+            tag_strat,
+            ctr: self.ctr,
+        })
+    }
 }
 
 /// A tagged component in the TAGE predictor. 
@@ -190,6 +338,51 @@ impl TAGEComponent {
             entry.useful = 0;
         }
     }
+
+    /// Save this component's tags, counters, and useful bits to `path` as
+    /// CBOR.
+    ///
+    /// This does *not* capture `csr` - [FoldedHistoryRegister] isn't
+    /// `Serialize` yet, so a restored component needs its folded history
+    /// re-warmed (e.g. by replaying a short tail of the trace through
+    /// [TAGEPredictor::update_history]) before predictions are
+    /// bit-identical to the state at checkpoint time.
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>)
+        -> Result<(), CheckpointError>
+    {
+        let snapshot = TAGEComponentSnapshot {
+            cfg: self.cfg.to_snapshot(),
+            data: self.data.clone(),
+        };
+        save_cbor(&snapshot, path)
+    }
+
+    /// Restore tags, counters, and useful bits previously saved with
+    /// [Self::save_checkpoint]. `index_strat`/`tag_strat` must be
+    /// registered under the same [StrategyId]s the checkpoint was taken
+    /// with. The folded-history CSR is rebuilt fresh (all zeroes) and
+    /// must be re-warmed by the caller; see [Self::save_checkpoint].
+    pub fn load_checkpoint(path: impl AsRef<Path>,
+        index_strat: IndexStrategy<TAGEComponent>,
+        tag_strat: TagStrategy<TAGEComponent>,
+    ) -> Result<Self, CheckpointError>
+    {
+        let snapshot: TAGEComponentSnapshot = load_cbor(path)?;
+        let cfg = snapshot.cfg.into_config(index_strat, tag_strat)?;
+        let csr = FoldedHistoryRegister::new(
+            cfg.size.ilog2() as usize,
+            cfg.ghr_range.clone()
+        );
+        Ok(Self { cfg, data: snapshot.data, csr })
+    }
+}
+
+/// A saved [TAGEComponent], as produced by
+/// [TAGEComponent::save_checkpoint].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TAGEComponentSnapshot {
+    cfg: TAGEComponentConfigSnapshot,
+    data: Vec<TAGEEntry>,
 }
 
 impl PredictorTable for TAGEComponent {
@@ -201,12 +394,15 @@ impl PredictorTable for TAGEComponent {
 
     fn get_index(&self, input: TAGEInputs) -> usize { 
         let res = match self.cfg.index_strat {
-            IndexStrategy::FromPc(func) => { 
+            IndexStrategy::FromPc(_, func) => {
                 (func)(self, input.pc)
             },
-            IndexStrategy::FromPhr(func) => { 
+            IndexStrategy::FromPcAndPath(_, func) => {
                 (func)(self, input.pc, input.phr)
             },
+            IndexStrategy::FromPcAndHistory(_, func) => {
+                (func)(self, input.pc, input.ghr)
+            },
         };
         res & self.index_mask()
     }
@@ -231,14 +427,79 @@ impl PredictorTable for TAGEComponent {
 }
 
 impl <'a> TaggedPredictorTable<'a> for TAGEComponent {
-    fn get_tag(&self, input: TAGEInputs) -> usize { 
+    fn get_tag(&self, input: TAGEInputs) -> usize {
         match self.cfg.tag_strat {
-            TagStrategy::FromPc(func) => (func)(self, input.pc)
+            TagStrategy::FromPc(_, func) => (func)(self, input.pc),
+            TagStrategy::FromPcAndPath(_, func) => (func)(self, input.pc, input.phr),
+            TagStrategy::FromPcAndHistory(_, func) => (func)(self, input.pc, input.ghr),
         }
-        //let pc_bits = (self.cfg.pc_sel_fn)(pc); 
+        //let pc_bits = (self.cfg.pc_sel_fn)(pc);
         //let ghist0_bits = self.csr.output_usize();
         //let ghist1_bits = self.csr.output_usize() << 1;
         //pc_bits ^ ghist0_bits ^ ghist1_bits
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn from_pc(_t: &TAGEBaseComponent, pc: usize) -> usize { pc }
+    fn from_path(_t: &TAGEBaseComponent, pc: usize, phr: &HistoryRegister) -> usize {
+        pc + 100 + (phr.read(0..=0)[0] as usize)
+    }
+    fn from_history(_t: &TAGEBaseComponent, pc: usize, ghr: &HistoryRegister) -> usize {
+        pc + 200 + (ghr.read(0..=0)[0] as usize)
+    }
+
+    fn base_table(index_strat: IndexStrategy<TAGEBaseComponent>) -> TAGEBaseComponent {
+        TAGEBaseConfig {
+            ctr: SaturatingCounterConfig {
+                max_t_state: 3,
+                max_n_state: 3,
+                default_state: Outcome::N,
+            },
+            // Large enough that `index_mask()` never clips the test's
+            // expected values, so the assertions below can check the raw
+            // dispatch result.
+            size: 256,
+            index_strat,
+        }.build()
+    }
+
+    #[test]
+    fn get_index_dispatches_to_the_configured_index_strategy() {
+        let mut phr = HistoryRegister::new(8);
+        phr.data_mut().set(0, true);
+        let mut ghr = HistoryRegister::new(8);
+        ghr.data_mut().set(0, true);
+        let input = TAGEInputs { pc: 1, phr: &phr, ghr: &ghr };
+
+        let pc_table = base_table(IndexStrategy::FromPc("pc", from_pc));
+        assert_eq!(pc_table.get_index(input.clone()), 1);
+
+        let path_table = base_table(IndexStrategy::FromPcAndPath("path", from_path));
+        assert_eq!(path_table.get_index(input.clone()), 1 + 100 + 1);
+
+        let history_table = base_table(IndexStrategy::FromPcAndHistory("history", from_history));
+        assert_eq!(history_table.get_index(input.clone()), 1 + 200 + 1);
+    }
+
+    #[test]
+    fn get_index_routes_path_and_history_bits_independently() {
+        let mut phr = HistoryRegister::new(8);
+        phr.data_mut().set(0, false);
+        let mut ghr = HistoryRegister::new(8);
+        ghr.data_mut().set(0, true);
+        let input = TAGEInputs { pc: 0, phr: &phr, ghr: &ghr };
+
+        // Same bit position, different registers - the path strategy must
+        // only ever see `phr`, never silently fall back to `ghr`.
+        let path_table = base_table(IndexStrategy::FromPcAndPath("path", from_path));
+        assert_eq!(path_table.get_index(input.clone()), 100);
+
+        let history_table = base_table(IndexStrategy::FromPcAndHistory("history", from_history));
+        assert_eq!(history_table.get_index(input), 201);
+    }
+}
+
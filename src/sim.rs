@@ -16,6 +16,11 @@
 use std::collections::*;
 use crate::direction::Outcome;
 
+/// Bound on the number of veneer-insertion passes in
+/// [Emitter::insert_veneers], used to detect a displacement limit too
+/// small to ever stabilize.
+const MAX_VENEER_ITERATIONS: usize = 64;
+
 /// A pre-determined pattern of branch outcomes to-be-associated with a 
 /// control-flow instruction in the IR. 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -26,6 +31,23 @@ pub enum BranchPattern {
     NotTakenPeriodic(usize),
     Pattern(&'static [Outcome]),
 }
+impl BranchPattern {
+    /// Return the pattern describing the logical inverse of this
+    /// condition, used when rewriting an out-of-range conditional branch
+    /// to branch over a local veneer jump (see [Emitter::insert_veneers]).
+    fn inverted(&self) -> Self {
+        match self {
+            Self::AlwaysTaken => Self::NeverTaken,
+            Self::NeverTaken => Self::AlwaysTaken,
+            Self::TakenPeriodic(n) => Self::NotTakenPeriodic(*n),
+            Self::NotTakenPeriodic(n) => Self::TakenPeriodic(*n),
+            Self::Pattern(seq) => {
+                let inverted: Vec<Outcome> = seq.iter().map(|o| !*o).collect();
+                Self::Pattern(Box::leak(inverted.into_boxed_slice()))
+            },
+        }
+    }
+}
 
 /// Representing a branch target in the IR.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -39,17 +61,18 @@ pub enum IRReloc {
 /// An instruction in the IR.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum IRInst {
-    /// A conditional branch instruction.
-    Branch(IRReloc),
+    /// A conditional branch instruction, with a [BranchPattern] describing
+    /// how its outcome evolves when unrolled by an [Interpreter].
+    Branch(IRReloc, BranchPattern),
 
     /// An unconditional branch instruction.
     Jump(IRReloc),
 
-    /// Increment the program counter by some value. 
+    /// Increment the program counter by some value.
     Pad(usize),
 
-    /// Increment the program counter until the value is aligned to some 
-    /// power of two. 
+    /// Increment the program counter until the value is aligned to some
+    /// power of two.
     PadAlign(usize),
 
     /// Terminate the program
@@ -57,9 +80,9 @@ pub enum IRInst {
 }
 impl IRInst {
     /// Returns the number of "virtual bytes" inhabited by this instruction.
-    pub fn size(&self, pc: usize) -> usize { 
-        match self { 
-            Self::Branch(_) => 1,
+    pub fn size(&self, pc: usize) -> usize {
+        match self {
+            Self::Branch(_, _) => 1,
             Self::Jump(_) => 1,
             Self::Pad(size) => *size,
             Self::PadAlign(aln) => {
@@ -84,26 +107,47 @@ impl Label {
     pub fn id(&self) -> usize { self.0 }
 }
 
-pub struct Emitter { 
-    /// The set of instructions. 
+pub struct Emitter {
+    /// The set of instructions.
     data: Vec<IRInst>,
-    /// The base address of the program. 
+    /// The base address of the program.
     base: usize,
     /// A counter used to allocate labels.
     next_label: usize,
-    /// A map from labels to offsets within `data`. 
+    /// A map from labels to offsets within `data`.
     labels: BTreeMap<usize, usize>,
+    /// Maximum displacement (in virtual bytes) allowed for a conditional
+    /// branch before a veneer is inserted. Defaults to unlimited.
+    max_branch_disp: usize,
+    /// Maximum displacement (in virtual bytes) allowed for an
+    /// unconditional jump before a veneer is inserted. Defaults to
+    /// unlimited.
+    max_jump_disp: usize,
 }
-impl Emitter { 
-    pub fn new(base: usize) -> Self { 
-        Self { 
+impl Emitter {
+    pub fn new(base: usize) -> Self {
+        Self {
             data: Vec::new(),
             next_label: 0,
             base,
             labels: BTreeMap::new(),
+            max_branch_disp: usize::MAX,
+            max_jump_disp: usize::MAX,
         }
     }
 
+    /// Set the maximum displacement (in virtual bytes) allowed for
+    /// conditional branches before [Emitter::emit] inserts a veneer.
+    pub fn set_max_branch_displacement(&mut self, limit: usize) {
+        self.max_branch_disp = limit;
+    }
+
+    /// Set the maximum displacement (in virtual bytes) allowed for
+    /// unconditional jumps before [Emitter::emit] inserts a veneer.
+    pub fn set_max_jump_displacement(&mut self, limit: usize) {
+        self.max_jump_disp = limit;
+    }
+
     /// Create a new label.
     pub fn create_label(&mut self) -> Label {
         let res = Label::new(self.next_label);
@@ -117,9 +161,16 @@ impl Emitter {
         self.labels.insert(label.id(), off);
     }
 
-    /// Emit a conditional branch to the provided [Label].
+    /// Emit a conditional branch to the provided [Label], always taken.
     pub fn branch_to_label(&mut self, tgt: Label) {
-        self.data.push(IRInst::Branch(IRReloc::Label(tgt)));
+        self.branch_to_label_with_pattern(tgt, BranchPattern::AlwaysTaken);
+    }
+
+    /// Emit a conditional branch to the provided [Label], with an explicit
+    /// [BranchPattern] describing how its outcome evolves when unrolled by
+    /// an [Interpreter].
+    pub fn branch_to_label_with_pattern(&mut self, tgt: Label, pattern: BranchPattern) {
+        self.data.push(IRInst::Branch(IRReloc::Label(tgt), pattern));
     }
 
     /// Emit an unconditional branch to the provided [Label].
@@ -154,10 +205,10 @@ impl Emitter {
     fn resolve_relocations(&mut self) {
         let pc_table = self.resolve_pc_table();
         for inst in self.data.iter_mut() {
-            match inst { 
+            match inst {
                 IRInst::Jump(ref mut reloc) |
-                IRInst::Branch(ref mut reloc) => {
-                    if let IRReloc::Label(lab) = reloc { 
+                IRInst::Branch(ref mut reloc, _) => {
+                    if let IRReloc::Label(lab) = reloc {
                         let idx = *self.labels.get(&lab.id()).unwrap();
                         let tgt_addr = pc_table[idx];
                         *reloc = IRReloc::Address(tgt_addr);
@@ -168,22 +219,299 @@ impl Emitter {
         }
     }
 
+    /// Detect branches/jumps whose displacement exceeds the configured
+    /// limit and rewrite them to go through an inserted veneer.
+    ///
+    /// An out-of-range [IRInst::Jump] is redirected through a local
+    /// trampoline jump that forwards to the true target. An out-of-range
+    /// [IRInst::Branch] has its sense inverted and is redirected to branch
+    /// over a local trampoline jump to the true target, landing on the
+    /// original fallthrough instruction otherwise - this preserves the
+    /// original taken/not-taken semantics while keeping the rewritten
+    /// branch itself in range.
+    ///
+    /// Inserting a veneer shifts every instruction after it, which can
+    /// push other branches out of range, so this runs to a fixpoint
+    /// (bounded by [MAX_VENEER_ITERATIONS] to detect a displacement limit
+    /// too small to ever stabilize).
+    fn insert_veneers(&mut self) {
+        for _ in 0..MAX_VENEER_ITERATIONS {
+            let pc_table = self.resolve_pc_table();
+            let labels = &self.labels;
+            let target_addr = |reloc: &IRReloc| -> usize {
+                match reloc {
+                    IRReloc::Label(lab) => pc_table[labels[&lab.id()]],
+                    IRReloc::Address(addr) => *addr,
+                }
+            };
+
+            let mut offender = None;
+            for (idx, inst) in self.data.iter().enumerate() {
+                let pc = pc_table[idx];
+                let (reloc, limit) = match inst {
+                    IRInst::Jump(reloc) => (reloc, self.max_jump_disp),
+                    IRInst::Branch(reloc, _) => (reloc, self.max_branch_disp),
+                    _ => continue,
+                };
+                if target_addr(reloc).abs_diff(pc) > limit {
+                    offender = Some((idx, *reloc));
+                    break;
+                }
+            }
+
+            let Some((idx, orig_reloc)) = offender else { return; };
+            let insert_at = idx + 1;
+
+            // Re-use the original (unresolved) reloc for the inserted
+            // veneer jump, rather than baking in the address it currently
+            // resolves to: the insertion below shifts every later
+            // instruction - including the real target, if it hasn't been
+            // emitted yet - so a label keeps tracking its true address
+            // while a pre-resolved address would go stale.
+            let (new_label, bind_offset) = match self.data[idx] {
+                IRInst::Jump(_) => {
+                    let veneer = self.create_label();
+                    self.data[idx] = IRInst::Jump(IRReloc::Label(veneer));
+                    (veneer, insert_at)
+                },
+                IRInst::Branch(_, pattern) => {
+                    let skip = self.create_label();
+                    self.data[idx] = IRInst::Branch(IRReloc::Label(skip), pattern.inverted());
+                    (skip, insert_at + 1)
+                },
+                _ => unreachable!(),
+            };
+
+            for off in self.labels.values_mut() {
+                if *off >= insert_at { *off += 1; }
+            }
+            self.data.insert(insert_at, IRInst::Jump(orig_reloc));
+            self.labels.insert(new_label.id(), bind_offset);
+        }
+    }
+
+    /// Remove redundant control flow left over from code generation.
+    ///
+    /// Handles two shapes:
+    ///
+    /// - An unconditional [IRInst::Jump] whose target is simply the next
+    ///   instruction is a no-op and is deleted outright.
+    /// - A conditional [IRInst::Branch] immediately followed by a
+    ///   [IRInst::Jump], where the branch's own (taken) target is simply
+    ///   the instruction after the jump, is a "branch over jump": the
+    ///   not-taken path always falls through the jump to its target
+    ///   anyway, so the pair collapses into a single branch - with
+    ///   inverted polarity - that targets the jump's own target directly.
+    ///
+    /// Runs before [Emitter::insert_veneers], which can introduce this
+    /// exact branch-over-jump shape on purpose to honor a displacement
+    /// limit; simplifying afterwards would undo that rewrite and
+    /// reintroduce the out-of-range branch it exists to avoid.
+    ///
+    /// Like [Emitter::insert_veneers], this works against labels (rather
+    /// than requiring relocations to already be resolved) so that
+    /// instructions can be deleted - and label offsets renumbered - one
+    /// at a time. Runs to a fixpoint; each successful rewrite strictly
+    /// shrinks the instruction count, so no iteration bound is needed.
+    fn simplify_branches(&mut self) {
+        loop {
+            let pc_table = self.resolve_pc_table();
+            let labels = &self.labels;
+            let target_addr = |reloc: &IRReloc| -> usize {
+                match reloc {
+                    IRReloc::Label(lab) => pc_table[labels[&lab.id()]],
+                    IRReloc::Address(addr) => *addr,
+                }
+            };
+
+            let mut redundant_jump = None;
+            for (idx, inst) in self.data.iter().enumerate() {
+                if let IRInst::Jump(reloc) = inst {
+                    if pc_table.get(idx + 1) == Some(&target_addr(reloc)) {
+                        redundant_jump = Some(idx);
+                        break;
+                    }
+                }
+            }
+            if let Some(idx) = redundant_jump {
+                self.data.remove(idx);
+                for off in self.labels.values_mut() {
+                    if *off > idx { *off -= 1; }
+                }
+                continue;
+            }
+
+            let mut branch_over_jump = None;
+            for idx in 0..self.data.len().saturating_sub(1) {
+                if let (IRInst::Branch(breloc, pattern), IRInst::Jump(jreloc)) =
+                    (&self.data[idx], &self.data[idx + 1])
+                {
+                    if pc_table.get(idx + 2) == Some(&target_addr(breloc)) {
+                        branch_over_jump = Some((idx, *jreloc, pattern.inverted()));
+                        break;
+                    }
+                }
+            }
+            if let Some((idx, jreloc, inverted)) = branch_over_jump {
+                self.data[idx] = IRInst::Branch(jreloc, inverted);
+                self.data.remove(idx + 1);
+                for off in self.labels.values_mut() {
+                    if *off > idx + 1 { *off -= 1; }
+                }
+                continue;
+            }
+
+            return;
+        }
+    }
+
+    /// Propagate statically-known [BranchPattern] outcomes through the
+    /// control-flow graph and thread/prune the instructions they make
+    /// dead, modeled on jump-threading.
+    ///
+    /// Unlike [Emitter::simplify_branches] and [Emitter::insert_veneers],
+    /// this pass is optional and not run automatically from
+    /// [Emitter::emit] - it changes which instructions exist in the
+    /// program, rather than just how a given control-flow shape is
+    /// encoded, so callers who author programs with compile-time-constant
+    /// branch behavior opt in explicitly by calling this before `emit`.
+    ///
+    /// Runs, to a fixpoint:
+    ///
+    /// - A [BranchPattern::AlwaysTaken] [IRInst::Branch] becomes an
+    ///   unconditional [IRInst::Jump] to the same target - its
+    ///   fallthrough edge is now unreachable.
+    /// - A [BranchPattern::NeverTaken] branch is deleted outright in
+    ///   favor of its fallthrough.
+    /// - A [IRInst::Jump] whose target is itself another `Jump` is
+    ///   threaded straight to that jump's own target.
+    ///
+    /// Finally, any instruction no longer reachable from `base` by
+    /// following fallthrough/jump/branch edges is pruned. Bound labels
+    /// referenced by surviving instructions are re-bound to their new
+    /// offsets, so [Emitter::resolve_relocations] can still run
+    /// unchanged afterwards.
+    pub fn propagate_static_outcomes(&mut self) {
+        loop {
+            let mut changed = false;
+
+            for idx in 0..self.data.len() {
+                if let IRInst::Branch(reloc, BranchPattern::AlwaysTaken) = self.data[idx] {
+                    self.data[idx] = IRInst::Jump(reloc);
+                    changed = true;
+                }
+            }
+
+            if let Some(idx) = self.data.iter()
+                .position(|inst| matches!(inst, IRInst::Branch(_, BranchPattern::NeverTaken)))
+            {
+                self.data.remove(idx);
+                for off in self.labels.values_mut() {
+                    if *off > idx { *off -= 1; }
+                }
+                changed = true;
+                continue;
+            }
+
+            let pc_table = self.resolve_pc_table();
+            let labels = &self.labels;
+            let index_of = |reloc: &IRReloc| -> Option<usize> {
+                match reloc {
+                    IRReloc::Label(lab) => labels.get(&lab.id()).copied(),
+                    IRReloc::Address(addr) => pc_table.iter().position(|pc| pc == addr),
+                }
+            };
+
+            let mut threaded = None;
+            for idx in 0..self.data.len() {
+                if let IRInst::Jump(reloc) = self.data[idx] {
+                    if let Some(tgt_idx) = index_of(&reloc) {
+                        if tgt_idx != idx {
+                            if let IRInst::Jump(next_reloc) = self.data[tgt_idx] {
+                                if next_reloc != reloc {
+                                    threaded = Some((idx, next_reloc));
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some((idx, next_reloc)) = threaded {
+                self.data[idx] = IRInst::Jump(next_reloc);
+                changed = true;
+            }
+
+            if !changed { break; }
+        }
+
+        self.prune_unreachable();
+    }
+
+    /// Drop every [IRInst] not reachable from `base` by following
+    /// fallthrough/jump/branch edges, compacting `data` and re-binding
+    /// surviving labels to their new offsets.
+    fn prune_unreachable(&mut self) {
+        let pc_table = self.resolve_pc_table();
+        let labels = &self.labels;
+        let index_of = |reloc: &IRReloc| -> Option<usize> {
+            match reloc {
+                IRReloc::Label(lab) => labels.get(&lab.id()).copied(),
+                IRReloc::Address(addr) => pc_table.iter().position(|pc| pc == addr),
+            }
+        };
+
+        let mut reachable = vec![false; self.data.len()];
+        let mut stack: Vec<usize> = if self.data.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(idx) = stack.pop() {
+            if idx >= self.data.len() || reachable[idx] { continue; }
+            reachable[idx] = true;
+            match &self.data[idx] {
+                IRInst::Jump(reloc) => {
+                    if let Some(t) = index_of(reloc) { stack.push(t); }
+                },
+                IRInst::Branch(reloc, _) => {
+                    if let Some(t) = index_of(reloc) { stack.push(t); }
+                    stack.push(idx + 1);
+                },
+                IRInst::Pad(_) | IRInst::PadAlign(_) => stack.push(idx + 1),
+                IRInst::Terminate => {},
+            }
+        }
+
+        let mut new_data = Vec::with_capacity(self.data.len());
+        let mut remap: Vec<Option<usize>> = vec![None; self.data.len()];
+        for (idx, inst) in self.data.iter().enumerate() {
+            if reachable[idx] {
+                remap[idx] = Some(new_data.len());
+                new_data.push(*inst);
+            }
+        }
+
+        self.labels = self.labels.iter()
+            .filter_map(|(id, off)| remap[*off].map(|new_off| (*id, new_off)))
+            .collect();
+        self.data = new_data;
+    }
+
     pub fn emit(&mut self) -> Program {
+        self.simplify_branches();
+        self.insert_veneers();
         self.resolve_relocations();
 
         let mut data: Vec<Branch> = Vec::new();
         let mut pc = self.base;
         for inst in self.data.iter() {
-            match inst { 
-                IRInst::Branch(IRReloc::Label(_)) |
+            match inst {
+                IRInst::Branch(IRReloc::Label(_), _) |
                 IRInst::Jump(IRReloc::Label(_)) => {
                     unreachable!("Unresolved label");
                 },
-                IRInst::Branch(IRReloc::Address(tgt)) => {
-                    data.push(Branch::new(pc, *tgt, BranchKind::Conditional));
+                IRInst::Branch(IRReloc::Address(tgt), pattern) => {
+                    data.push(Branch::new(pc, *tgt, BranchKind::Conditional, *pattern));
                 },
                 IRInst::Jump(IRReloc::Address(tgt)) => {
-                    data.push(Branch::new(pc, *tgt, BranchKind::Unconditional));
+                    data.push(Branch::new(pc, *tgt, BranchKind::Unconditional, BranchPattern::AlwaysTaken));
                 },
                 _ => {},
             }
@@ -201,14 +529,18 @@ pub enum BranchKind {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct Branch { 
+pub struct Branch {
     addr: usize,
     tgt: usize,
     kind: BranchKind,
+    /// The pattern used to resolve this branch's outcome when unrolled by
+    /// an [Interpreter]. Always [BranchPattern::AlwaysTaken] for a
+    /// [BranchKind::Unconditional] branch.
+    pattern: BranchPattern,
 }
 impl Branch {
-    pub fn new(addr: usize, tgt: usize, kind: BranchKind) -> Self { 
-        Self { addr, tgt, kind }
+    pub fn new(addr: usize, tgt: usize, kind: BranchKind, pattern: BranchPattern) -> Self {
+        Self { addr, tgt, kind, pattern }
     }
 }
 
@@ -218,14 +550,113 @@ pub struct Program {
     data: Vec<Branch>,
 }
 impl Program {
-    pub fn new(base: usize, data: Vec<Branch>) -> Self { 
-        Self { 
+    pub fn new(base: usize, data: Vec<Branch>) -> Self {
+        Self {
             base,
             data,
         }
     }
 }
 
+/// A single resolved dynamic branch event produced by an [Interpreter].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BranchEvent {
+    /// The program counter of the branch instruction.
+    pub pc: usize,
+    /// The target address evaluated for this branch.
+    pub tgt: usize,
+    /// The outcome resolved for this branch.
+    pub outcome: Outcome,
+    /// The type of branch.
+    pub kind: BranchKind,
+}
+
+/// Per-branch state needed to resolve periodic/[BranchPattern::Pattern]
+/// patterns, keyed by the address of the branch.
+#[derive(Clone, Debug, Default)]
+struct PatternState {
+    visits: usize,
+}
+
+/// Walks a [Program] starting at its base address, consuming each
+/// branch's [BranchPattern] to unroll a linear sequence of dynamic
+/// [BranchEvent]s - in effect, a ground-truth trace.
+///
+/// When execution runs off the last branch in the program, it wraps back
+/// around to the program's base address, so loops (like the one formed by
+/// a trailing [Emitter::branch_to_label] back to the start) unroll for as
+/// long as the caller asks.
+pub struct Interpreter<'p> {
+    program: &'p Program,
+    pc: usize,
+    state: BTreeMap<usize, PatternState>,
+}
+impl <'p> Interpreter<'p> {
+    pub fn new(program: &'p Program) -> Self {
+        Self { program, pc: program.base, state: BTreeMap::new() }
+    }
+
+    /// Find the first branch at or after `pc`, in program order.
+    fn next_branch_at_or_after(&self, pc: usize) -> Option<&'p Branch> {
+        self.program.data.iter().find(|b| b.addr >= pc)
+    }
+
+    /// Resolve the next outcome for `branch`, advancing its visit counter.
+    fn resolve_outcome(&mut self, branch: &Branch) -> Outcome {
+        let state = self.state.entry(branch.addr).or_default();
+        let visit = state.visits;
+        state.visits += 1;
+        match branch.pattern {
+            BranchPattern::AlwaysTaken => Outcome::T,
+            BranchPattern::NeverTaken => Outcome::N,
+            BranchPattern::TakenPeriodic(n) => {
+                if visit % n == 0 { Outcome::T } else { Outcome::N }
+            },
+            BranchPattern::NotTakenPeriodic(n) => {
+                if visit % n == 0 { Outcome::N } else { Outcome::T }
+            },
+            BranchPattern::Pattern(seq) => seq[visit % seq.len()],
+        }
+    }
+
+    /// Unroll up to `n` dynamic branch events.
+    pub fn run(&mut self, n: usize) -> Vec<BranchEvent> {
+        let mut out = Vec::with_capacity(n);
+        for _ in 0..n {
+            let branch = match self.next_branch_at_or_after(self.pc) {
+                Some(b) => b,
+                None => {
+                    self.pc = self.program.base;
+                    match self.next_branch_at_or_after(self.pc) {
+                        Some(b) => b,
+                        None => break,
+                    }
+                },
+            };
+
+            let outcome = self.resolve_outcome(branch);
+            out.push(BranchEvent {
+                pc: branch.addr,
+                tgt: branch.tgt,
+                outcome,
+                kind: branch.kind,
+            });
+
+            self.pc = match (branch.kind, outcome) {
+                (BranchKind::Unconditional, _) => branch.tgt,
+                (BranchKind::Conditional, Outcome::T) => branch.tgt,
+                (BranchKind::Conditional, Outcome::N) => branch.addr + branch.size_hint(),
+            };
+        }
+        out
+    }
+}
+impl Branch {
+    /// The number of "virtual bytes" this branch occupies, used to
+    /// compute the fallthrough address when its outcome isn't taken.
+    fn size_hint(&self) -> usize { 1 }
+}
+
 
 #[cfg(test)]
 mod test { 
@@ -250,6 +681,125 @@ mod test {
         println!("{:x?}", p);
 
     }
+
+    #[test]
+    fn interpreter_loop() {
+        let mut e = Emitter::new(0x1000_0000);
+        let start = e.create_label();
+        e.bind_label(start);
+        e.branch_to_label_with_pattern(start, BranchPattern::TakenPeriodic(2));
+
+        let p = e.emit();
+        let mut interp = Interpreter::new(&p);
+        let events = interp.run(4);
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].outcome, Outcome::T);
+        assert_eq!(events[1].outcome, Outcome::N);
+        assert_eq!(events[2].outcome, Outcome::T);
+        assert_eq!(events[3].outcome, Outcome::N);
+    }
+
+    #[test]
+    fn veneer_insertion() {
+        let mut e = Emitter::new(0x1000_0000);
+        e.set_max_branch_displacement(0x10);
+
+        let far = e.create_label();
+        e.branch_to_label(far);
+        e.pad(0x1000);
+        e.bind_label(far);
+        e.pad(1);
+
+        let p = e.emit();
+        let mut interp = Interpreter::new(&p);
+        let events = interp.run(2);
+
+        // The rewritten branch inverts sense (now NeverTaken) and falls
+        // through into the inserted veneer jump, which must still land on
+        // the original far target - itself shifted forward by one slot
+        // once the veneer jump was inserted ahead of it.
+        assert_eq!(events[0].outcome, Outcome::N);
+        assert_eq!(events[1].tgt, 0x1000_0000 + 1 + 1 + 0x1000);
+    }
+
+    #[test]
+    fn redundant_fallthrough_jump_removed() {
+        let mut e = Emitter::new(0x1000_0000);
+        let next = e.create_label();
+        e.jump_to_label(next);
+        e.bind_label(next);
+        e.pad(1);
+
+        let p = e.emit();
+        assert_eq!(p.data.len(), 0);
+    }
+
+    #[test]
+    fn branch_over_jump_collapsed() {
+        let mut e = Emitter::new(0x1000_0000);
+        let skip = e.create_label();
+        let far = e.create_label();
+
+        e.branch_to_label(skip);
+        e.jump_to_label(far);
+        e.bind_label(skip);
+        e.pad(1);
+        e.bind_label(far);
+        e.pad(1);
+
+        let p = e.emit();
+
+        // The branch-over-jump pair collapses into a single inverted
+        // branch targeting `far` directly.
+        assert_eq!(p.data.len(), 1);
+        assert_eq!(p.data[0].kind, BranchKind::Conditional);
+        assert_eq!(p.data[0].pattern, BranchPattern::NeverTaken);
+        assert_eq!(p.data[0].tgt, 0x1000_0000 + 1 + 1);
+    }
+
+    #[test]
+    fn always_taken_threaded_to_jump_and_dead_code_pruned() {
+        let mut e = Emitter::new(0x1000_0000);
+        let dead = e.create_label();
+        let live = e.create_label();
+
+        e.branch_to_label_with_pattern(live, BranchPattern::AlwaysTaken);
+        e.jump_to_label(dead); // unreachable once the branch above always taken
+        e.bind_label(dead);
+        e.pad(1);
+        e.bind_label(live);
+        e.pad(1);
+
+        e.propagate_static_outcomes();
+
+        assert_eq!(e.data.len(), 2);
+        assert!(matches!(e.data[0], IRInst::Jump(_)));
+        assert!(matches!(e.data[1], IRInst::Pad(1)));
+    }
+
+    #[test]
+    fn jump_chain_threaded_and_dead_links_pruned() {
+        let mut e = Emitter::new(0x1000_0000);
+        let mid = e.create_label();
+        let end = e.create_label();
+
+        e.jump_to_label(mid);
+        e.pad(1); // unreachable: nothing falls through an unconditional jump
+        e.bind_label(mid);
+        e.jump_to_label(end);
+        e.pad(1); // unreachable once the first jump threads past `mid`
+        e.bind_label(end);
+        e.pad(1);
+
+        e.propagate_static_outcomes();
+
+        // Threaded straight to `end`; the now-unreferenced `mid` link is
+        // pruned along with the dead padding around it.
+        assert_eq!(e.data.len(), 2);
+        assert_eq!(e.data[0], IRInst::Jump(IRReloc::Label(end)));
+        assert!(matches!(e.data[1], IRInst::Pad(1)));
+    }
 }
 
 
@@ -1,11 +1,21 @@
 
+#[path = "tage/component.rs"]
 pub mod component;
-pub use component::*;
+pub use component::TAGEBaseComponent;
+pub use component::{ FoldedHistory, FoldedHistorySnapshot, TAGEEntry, TAGEComponent, TAGEComponentSnapshot };
+
+#[path = "tage/loop_predictor.rs"]
+pub mod loop_predictor;
+pub use loop_predictor::*;
+
+#[path = "tage/sc.rs"]
+pub mod sc;
+pub use sc::*;
 
 use bitvec::prelude::*;
 use crate::history::*;
 use crate::direction::*;
-use crate::predictor::*;
+use crate::predictor::{ PredictorTable, TaggedPredictorTable };
 use std::ops::RangeInclusive;
 use rand::prelude;
 use rand::distributions::{ WeightedIndex, Distribution };
@@ -25,8 +35,24 @@ pub struct TAGEPrediction {
     pub provider: TAGEProvider,
     /// Alternate component used to provide a prediction
     pub alt_provider: TAGEProvider,
-    /// A predicted direction
+    /// The final predicted direction, after the use-alt-on-na fallback
+    /// (see [TAGEPredictor::predict]) has had a chance to override it.
     pub outcome: Outcome,
+    /// The raw, unmodified prediction from `provider`.
+    pub provider_outcome: Outcome,
+    /// The raw, unmodified prediction from `alt_provider`.
+    pub alt_outcome: Outcome,
+    /// Whether `outcome` was taken from `alt_provider` via the
+    /// use-alt-on-na fallback, rather than from `provider` directly.
+    pub used_alt: bool,
+    /// What [TAGEPredictor::predict] itself decided, before
+    /// [LTAGEPredictor::predict] had a chance to override `outcome` with
+    /// a confident [LoopPredictor] prediction. Always equal to `outcome`
+    /// unless `loop_override` is set.
+    pub tage_outcome: Outcome,
+    /// Whether `outcome` was taken from a confident [LoopPredictor] entry,
+    /// overriding `tage_outcome`.
+    pub loop_override: bool,
 }
 
 /// The "TAgged GEometric history length" predictor. 
@@ -34,19 +60,90 @@ pub struct TAGEPrediction {
 /// See "A case for (partially) TAgged GEometric history length branch 
 /// prediction" (Seznec, 2006).
 ///
-pub struct TAGEPredictor { 
+pub struct TAGEPredictor {
     /// Base component
     pub base: TAGEBaseComponent,
 
     /// Tagged components
     pub comp: Vec<TAGEComponent>,
+
+    /// log2 of the number of [TAGEPredictor::update] calls between
+    /// periodic 'useful'-bit resets (e.g. `18` means a reset roughly
+    /// every 256K branches).
+    ///
+    /// NOTE: The wired TAGE implementation under [crate::predictor::tage]
+    /// groups knobs like this into a `TAGEConfig` built ahead of time;
+    /// this predictor has no such config type (it's assembled
+    /// imperatively via [TAGEPredictor::new] and
+    /// [TAGEPredictor::add_component]), so the knob lives directly on
+    /// the predictor instead.
+    pub log_reset_period: usize,
+
+    /// Number of [TAGEPredictor::update] calls observed so far.
+    tick: u64,
+
+    /// Number of periodic useful-bit resets performed so far - alternates
+    /// which half of each 'useful' counter gets cleared.
+    resets: u64,
+
+    /// Signed counters deciding whether to trust a weak/newly-allocated
+    /// provider entry or fall back to `alt_provider`, indexed by a few
+    /// low bits of the program counter.
+    use_alt_on_na: Vec<i8>,
+
+    /// Number of predictions where the use-alt-on-na fallback overrode
+    /// the provider's own prediction.
+    alt_used: u64,
 }
 impl TAGEPredictor {
-    /// Create a new predictor with some base component. The user is expected 
+    /// Create a new predictor with some base component. The user is expected
     /// to add tagged components with [TAGEPredictor::add_component].
     pub fn new(base: TAGEBaseComponent) -> Self {
-        Self { 
-            base, comp: Vec::new()
+        Self {
+            base, comp: Vec::new(),
+            log_reset_period: 18,
+            tick: 0,
+            resets: 0,
+            use_alt_on_na: vec![0i8; 16],
+            alt_used: 0,
+        }
+    }
+
+    /// Index into [TAGEPredictor::use_alt_on_na], selected by a few low
+    /// bits of the program counter.
+    fn use_alt_on_na_index(pc: usize) -> usize {
+        pc & 0b1111
+    }
+
+    /// A provider prediction is "weak/newly-allocated" when its
+    /// saturating counter sits at the weakest confidence level (having
+    /// just crossed over) and it hasn't yet proven useful. The base
+    /// component has no 'useful' bit and is never considered weak.
+    fn provider_is_weak(&self, pc: usize, provider: TAGEProvider) -> bool {
+        match provider {
+            TAGEProvider::Base => false,
+            TAGEProvider::Tagged(idx) => {
+                let index = self.comp[idx].get_index(pc);
+                let entry = self.comp[idx].get_entry(index);
+                entry.ctr.magnitude() == 0 && entry.useful == 0
+            },
+        }
+    }
+
+    /// Return how many predictions have been overridden by the
+    /// use-alt-on-na fallback so far.
+    pub fn alt_used(&self) -> u64 { self.alt_used }
+
+    /// Return the confidence (counter magnitude) behind `provider`'s
+    /// prediction at `pc`, used by [StatisticalCorrector] to scale its
+    /// own contribution to the provider's direction.
+    fn provider_confidence(&self, pc: usize, provider: TAGEProvider) -> u8 {
+        match provider {
+            TAGEProvider::Base => self.base.get_entry(self.base.get_index(pc)).magnitude(),
+            TAGEProvider::Tagged(idx) => {
+                let index = self.comp[idx].get_index(pc);
+                self.comp[idx].get_entry(index).ctr.magnitude()
+            },
         }
     }
 
@@ -102,7 +199,8 @@ impl TAGEPredictor {
         // program counter has its 'useful' bits set to zero. 
         let mut candidates: Vec<usize> = Vec::new();
         for idx in provider_range {
-            let entry = self.comp[idx].get_entry(pc);
+            let index = self.comp[idx].get_index(pc);
+            let entry = self.comp[idx].get_entry(index);
             if entry.useful == 0 {
                 candidates.push(idx);
             }
@@ -137,17 +235,17 @@ impl TAGEPredictor {
     {
         let mut entries = Vec::new();
         for component in self.comp.iter() {
-            entries.push(component.get_entry(pc));
+            let index = component.get_index(pc);
+            entries.push(component.get_entry(index));
         }
-        (self.base.get_entry(pc), entries)
+        let base_index = self.base.get_index(pc);
+        (self.base.get_entry(base_index), entries)
     }
 
-    pub fn get_all_tags(&self, pc: usize) -> Vec<usize> { 
-        let mut tags = Vec::new();
-        for component in self.comp.iter() {
-            tags.push(0);
-        }
-        tags
+    /// Compute the tag each tagged component expects at `pc`, from that
+    /// component's own folded-history CSRs (see [TAGEComponent::get_tag]).
+    pub fn get_all_tags(&self, pc: usize) -> Vec<usize> {
+        self.comp.iter().map(|component| component.get_tag(pc)).collect()
     }
 
     pub fn predict(&self, pc: usize) -> TAGEPrediction {
@@ -159,16 +257,37 @@ impl TAGEPredictor {
             provider: TAGEProvider::Base,
             alt_provider: TAGEProvider::Base,
             outcome: base.predict(),
+            provider_outcome: base.predict(),
+            alt_outcome: base.predict(),
+            used_alt: false,
+            tage_outcome: base.predict(),
+            loop_override: false,
         };
 
-        for ((idx, entry), tag) in tagged_iter { 
+        for ((idx, entry), tag) in tagged_iter {
             let hit = if let Some(v) = entry.tag { v == *tag } else { false };
-            if hit { 
+            if hit {
                 result.alt_provider = result.provider;
+                result.alt_outcome = result.provider_outcome;
                 result.provider = TAGEProvider::Tagged(idx);
-                result.outcome = entry.predict();
+                result.provider_outcome = entry.predict();
             }
         }
+        result.outcome = result.provider_outcome;
+
+        // Use-alt-on-na: a newly-allocated/weak provider entry is
+        // effectively a coin flip, so once the relevant use_alt_on_na
+        // counter has learned that the alternate provider tends to be
+        // right in these cases, prefer it instead.
+        if self.provider_is_weak(pc, result.provider) {
+            let na_idx = Self::use_alt_on_na_index(pc);
+            if self.use_alt_on_na[na_idx] > 0 {
+                result.outcome = result.alt_outcome;
+                result.used_alt = true;
+            }
+        }
+
+        result.tage_outcome = result.outcome;
         result
     }
 
@@ -181,13 +300,28 @@ impl TAGEPredictor {
         // Try to allocate a new entry
         if let Some(idx) = self.select_alloc_candidate(pc, prediction.provider) {
             println!("[*] Allocated in comp{}", idx);
-            let new_entry = self.comp[idx].get_entry_mut(pc);
+            let tag = self.comp[idx].get_tag(pc);
+            let new_index = self.comp[idx].get_index(pc);
+            let new_entry = self.comp[idx].get_entry_mut(new_index);
             new_entry.useful = 1;
-            new_entry.tag = Some(0);
+            new_entry.tag = Some(tag);
             new_entry.ctr.update(outcome);
-        } 
-        // Otherwise, use some strategy to age all of the entries
-        else { 
+        }
+        // Otherwise, allocation failed because every eligible
+        // longer-history entry was still useful - age them down by one
+        // so one may free up for a future allocation.
+        else if !matches!(prediction.provider, TAGEProvider::Tagged(0)) {
+            let provider_range = match prediction.provider {
+                TAGEProvider::Base => 0..=self.shortest_tagged_component(),
+                TAGEProvider::Tagged(idx) => 0..=(idx - 1),
+            };
+            for idx in provider_range {
+                let index = self.comp[idx].get_index(pc);
+                let entry = self.comp[idx].get_entry_mut(index);
+                if entry.useful > 0 {
+                    entry.useful -= 1;
+                }
+            }
         }
 
     }
@@ -200,11 +334,13 @@ impl TAGEPredictor {
     {
         match prediction.provider {
             TAGEProvider::Base => {
-                let entry = self.base.get_entry_mut(pc);
+                let index = self.base.get_index(pc);
+                let entry = self.base.get_entry_mut(index);
                 entry.update(outcome);
             },
             TAGEProvider::Tagged(idx) => {
-                let entry = self.comp[idx].get_entry_mut(pc);
+                let index = self.comp[idx].get_index(pc);
+                let entry = self.comp[idx].get_entry_mut(index);
                 entry.increment_useful();
                 entry.ctr.update(outcome);
             },
@@ -218,23 +354,231 @@ impl TAGEPredictor {
     )
     {
         let misprediction = prediction.outcome != outcome;
+
+        if self.provider_is_weak(pc, prediction.provider) {
+            let provider_correct = prediction.provider_outcome == outcome;
+            let alt_correct = prediction.alt_outcome == outcome;
+            let na_idx = Self::use_alt_on_na_index(pc);
+            if alt_correct && !provider_correct {
+                self.use_alt_on_na[na_idx] = self.use_alt_on_na[na_idx].saturating_add(1);
+            } else if provider_correct && !alt_correct {
+                self.use_alt_on_na[na_idx] = self.use_alt_on_na[na_idx].saturating_sub(1);
+            }
+        }
+        if prediction.used_alt {
+            self.alt_used += 1;
+        }
+
         if misprediction {
             self.update_on_misprediction(pc, prediction, outcome);
-        } 
+        }
         else {
             self.update_on_correct_prediction(pc, prediction, outcome);
         }
 
+        self.tick += 1;
+        let period_mask = (1u64 << self.log_reset_period) - 1;
+        if self.tick & period_mask == 0 {
+            self.reset_useful_bits();
+        }
+    }
+
+    /// Gracefully reset 'useful' bits across every tagged component: on
+    /// even reset cycles, clear the high bit of each counter; on odd
+    /// cycles, clear the low bit. Alternating halves means a reset never
+    /// wipes out every entry's usefulness at once.
+    fn reset_useful_bits(&mut self) {
+        let clear_high = self.resets % 2 == 0;
+        for comp in self.comp.iter_mut() {
+            for entry in comp.data.iter_mut() {
+                if clear_high {
+                    entry.useful &= 0b01;
+                } else {
+                    entry.useful &= 0b10;
+                }
+            }
+        }
+        self.resets += 1;
     }
 
     pub fn update_history(&mut self, ghr: &GlobalHistoryRegister) {
         for comp in self.comp.iter_mut() {
-            comp.csr.update(ghr);
+            comp.update_history(ghr);
+        }
+    }
+
+    /// Capture the speculatively-updated history state behind this
+    /// predictor: `ghr` and every tagged component's folded-history CSRs.
+    /// Saturating counters, useful bits, and tags are only ever changed
+    /// by [TAGEPredictor::update] at retirement, so they aren't part of
+    /// this snapshot.
+    pub fn checkpoint(&self, ghr: &GlobalHistoryRegister) -> TAGESnapshot {
+        TAGESnapshot {
+            ghr: ghr.checkpoint(),
+            comp: self.comp.iter().map(|c| c.checkpoint()).collect(),
+        }
+    }
+
+    /// Restore history state previously captured with
+    /// [TAGEPredictor::checkpoint].
+    pub fn restore(&mut self, snapshot: &TAGESnapshot, ghr: &mut GlobalHistoryRegister) {
+        ghr.restore(&snapshot.ghr);
+        for (comp, comp_snap) in self.comp.iter_mut().zip(snapshot.comp.iter()) {
+            comp.restore(comp_snap);
         }
     }
 
+    /// Roll speculative history back to `snapshot`, as a pipeline would on
+    /// a misprediction. An alias for [TAGEPredictor::restore] under the
+    /// name pipelines usually give this operation.
+    pub fn squash(&mut self, snapshot: &TAGESnapshot, ghr: &mut GlobalHistoryRegister) {
+        self.restore(snapshot, ghr);
+    }
+
+}
+
+/// A saved [TAGEPredictor] history state, as produced by
+/// [TAGEPredictor::checkpoint]. Intended use: checkpoint before
+/// speculatively advancing history off of a predicted outcome, then
+/// [TAGEPredictor::squash] back to it if the prediction turns out wrong.
+#[derive(Clone, Debug)]
+pub struct TAGESnapshot {
+    ghr: GlobalHistorySnapshot,
+    comp: Vec<TAGEComponentSnapshot>,
 }
 
+/// An L-TAGE predictor: a [TAGEPredictor] paired with a [LoopPredictor]
+/// that overrides it on regularly-iterating loop branches.
+///
+/// See "A case for (partially) TAgged GEometric history length branch
+/// prediction" (Seznec, 2006), section on the loop predictor component.
+pub struct LTAGEPredictor {
+    /// The underlying TAGE predictor.
+    pub tage: TAGEPredictor,
+    /// The loop predictor, consulted to override `tage` on confident
+    /// loop-exit predictions.
+    pub loop_pred: LoopPredictor,
+
+    /// Number of predictions where the loop predictor overrode `tage` and
+    /// the resulting prediction was correct.
+    loop_hits: u64,
+    /// Number of predictions overridden by the loop predictor.
+    loop_overrides: u64,
+}
+impl LTAGEPredictor {
+    /// Create a new predictor from an already-assembled [TAGEPredictor]
+    /// and a loop table of `loop_size` entries, indexed by folding a
+    /// program counter value through `loop_index_fn`.
+    pub fn new(
+        tage: TAGEPredictor,
+        loop_size: usize,
+        loop_index_fn: fn(usize) -> usize,
+    ) -> Self
+    {
+        Self {
+            tage,
+            loop_pred: LoopPredictor::new(loop_size, loop_index_fn),
+            loop_hits: 0,
+            loop_overrides: 0,
+        }
+    }
+
+    /// Predict the outcome of the branch at `pc`, letting the loop
+    /// predictor override TAGE's own prediction when it has maximal
+    /// confidence in an iteration count for this branch.
+    pub fn predict(&self, pc: usize) -> TAGEPrediction {
+        let mut prediction = self.tage.predict(pc);
+        if let Some(loop_outcome) = self.loop_pred.predict(pc) {
+            prediction.outcome = loop_outcome;
+            prediction.loop_override = true;
+        }
+        prediction
+    }
+
+    /// Update both the loop predictor and the underlying TAGE predictor
+    /// with a resolved branch outcome.
+    pub fn update(&mut self, pc: usize, prediction: TAGEPrediction, outcome: Outcome) {
+        if prediction.loop_override {
+            self.loop_overrides += 1;
+            if prediction.outcome == outcome {
+                self.loop_hits += 1;
+            }
+        }
+        self.loop_pred.update(pc, outcome);
+
+        // Feed TAGE its own prediction, not the (possibly loop-overridden)
+        // final outcome, so its allocation/useful-bit bookkeeping reflects
+        // what it actually predicted.
+        let mut tage_prediction = prediction;
+        tage_prediction.outcome = prediction.tage_outcome;
+        self.tage.update(pc, tage_prediction, outcome);
+    }
+
+    pub fn update_history(&mut self, ghr: &GlobalHistoryRegister) {
+        self.tage.update_history(ghr);
+    }
+
+    /// Number of predictions overridden by the loop predictor so far.
+    pub fn loop_overrides(&self) -> u64 { self.loop_overrides }
+
+    /// Number of loop-predictor overrides that were correct.
+    pub fn loop_hits(&self) -> u64 { self.loop_hits }
+}
+
+/// The combined result of a [TAGESCPredictor] prediction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TAGESCPrediction {
+    /// The underlying TAGE prediction.
+    pub tage: TAGEPrediction,
+    /// The final predicted direction, after the statistical corrector has
+    /// had a chance to override `tage.outcome`.
+    pub outcome: Outcome,
+    /// Whether the statistical corrector overrode `tage.outcome`.
+    pub sc_override: bool,
+}
+
+/// A TAGE-SC-L-style predictor: a [TAGEPredictor] whose outcome is
+/// post-processed by a [StatisticalCorrector].
+pub struct TAGESCPredictor {
+    /// The underlying TAGE predictor.
+    pub tage: TAGEPredictor,
+    /// The statistical corrector stage.
+    pub sc: StatisticalCorrector,
+}
+impl TAGESCPredictor {
+    pub fn new(tage: TAGEPredictor, sc: StatisticalCorrector) -> Self {
+        Self { tage, sc }
+    }
+
+    /// Predict the outcome of the branch at `pc`, letting the statistical
+    /// corrector override TAGE's own prediction when its ensemble
+    /// disagrees with enough confidence.
+    pub fn predict(&self, pc: usize, phr: &GlobalHistoryRegister) -> TAGESCPrediction {
+        let tage = self.tage.predict(pc);
+        let confidence = self.tage.provider_confidence(pc, tage.provider);
+        let (outcome, sc_override) = self.sc.predict(pc, phr, tage.outcome, confidence);
+        TAGESCPrediction { tage, outcome, sc_override }
+    }
+
+    /// Update the statistical corrector and the underlying TAGE predictor
+    /// with a resolved branch outcome.
+    pub fn update(
+        &mut self,
+        pc: usize,
+        phr: &GlobalHistoryRegister,
+        prediction: TAGESCPrediction,
+        outcome: Outcome,
+    )
+    {
+        self.sc.update(pc, phr, prediction.outcome, prediction.sc_override, outcome);
+        self.tage.update(pc, prediction.tage, outcome);
+    }
+
+    pub fn update_history(&mut self, ghr: &GlobalHistoryRegister) {
+        self.tage.update_history(ghr);
+        self.sc.update_history(ghr);
+    }
+}
 
 
 
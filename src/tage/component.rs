@@ -1,7 +1,7 @@
 
 use crate::direction::*;
 use crate::history::*;
-use crate::predictor::*;
+use crate::predictor::{ PredictorTable, TaggedPredictorTable };
 use std::ops::RangeInclusive;
 
 /// A TAGE "base predictor" component. 
@@ -32,26 +32,91 @@ impl TAGEBaseComponent {
     }
 }
 impl PredictorTable for TAGEBaseComponent {
-    type Input = usize;
+    type Input<'a> = usize;
+    type Index = usize;
     type Entry = SaturatingCounter;
 
     fn size(&self) -> usize { self.size }
-    fn get_index(&self, pc: usize) -> usize { 
+    fn get_index(&self, pc: usize) -> usize {
         (self.index_fn)(pc) & self.index_mask()
     }
-    fn get_entry(&self, pc: usize) -> &SaturatingCounter { 
-        let index = self.get_index(pc);
-        &self.data[index]
+    fn get_entry(&self, idx: usize) -> &SaturatingCounter {
+        &self.data[idx]
     }
-    fn get_entry_mut(&mut self, pc: usize) -> &mut SaturatingCounter { 
-        let index = self.get_index(pc);
-        &mut self.data[index]
+    fn get_entry_mut(&mut self, idx: usize) -> &mut SaturatingCounter {
+        &mut self.data[idx]
     }
 
 }
 
 
-/// An entry in some [TAGEComponent]. 
+/// A folded-history circular shift register, as used by the original
+/// TAGE design to incrementally compress a window of global history down
+/// to a fixed number of bits without re-reading and re-folding the whole
+/// window on every update.
+///
+/// `orig_length` is the number of history bits being folded, and
+/// `comp_length` is the width (in bits) of the folded output. `outpoint`
+/// is the bit position - within the folded output - where the
+/// newly-retired history bit is reinserted.
+#[derive(Clone, Debug)]
+pub struct FoldedHistory {
+    /// The current compressed value.
+    comp: usize,
+    /// The number of history bits being folded.
+    orig_length: usize,
+    /// The width (in bits) of the folded output.
+    comp_length: usize,
+    /// `orig_length % comp_length`.
+    outpoint: usize,
+}
+impl FoldedHistory {
+    pub fn new(orig_length: usize, comp_length: usize) -> Self {
+        Self {
+            comp: 0,
+            orig_length,
+            comp_length,
+            outpoint: orig_length % comp_length,
+        }
+    }
+
+    /// Return the current folded value.
+    pub fn output(&self) -> usize { self.comp }
+
+    /// Return the number of history bits this CSR folds.
+    pub fn history_length(&self) -> usize { self.orig_length }
+
+    /// Incrementally fold in the newest global-history bit `h_new`,
+    /// retiring the bit `h_out` shifting out at position `orig_length`.
+    pub fn update(&mut self, h_new: bool, h_out: bool) {
+        self.comp = (self.comp << 1) | (h_new as usize);
+        self.comp ^= (h_out as usize) << self.outpoint;
+        self.comp ^= self.comp >> self.comp_length;
+        self.comp &= (1 << self.comp_length) - 1;
+    }
+
+    /// Capture the current folded value for later [Self::restore]. Since
+    /// the fold is lossy-incremental, the snapshot must hold `comp`
+    /// directly - there's no way to recompute it from history alone.
+    pub fn checkpoint(&self) -> FoldedHistorySnapshot {
+        FoldedHistorySnapshot { comp: self.comp }
+    }
+
+    /// Restore a folded value previously captured with
+    /// [Self::checkpoint].
+    pub fn restore(&mut self, snapshot: &FoldedHistorySnapshot) {
+        self.comp = snapshot.comp;
+    }
+}
+
+/// A saved [FoldedHistory] state, as produced by
+/// [FoldedHistory::checkpoint].
+#[derive(Clone, Copy, Debug)]
+pub struct FoldedHistorySnapshot {
+    comp: usize,
+}
+
+/// An entry in some [TAGEComponent].
 #[derive(Clone, Debug)]
 pub struct TAGEEntry {
     pub ctr: SaturatingCounter,
@@ -107,60 +172,103 @@ pub struct TAGEComponent {
     pub tag_bits: usize,
     /// Function selecting relevant program counter bits
     pub pc_sel_fn: fn(usize) -> usize,
-    /// Folded global history
-    pub csr: FoldedHistoryRegister,
+    /// Folded global history used to form a table index.
+    pub idx_csr: FoldedHistory,
+    /// Folded global history used to form a tag, at `tag_bits` width.
+    pub tag_csr1: FoldedHistory,
+    /// Folded global history used to form a tag, at `tag_bits - 1` width -
+    /// XORed with a different shift than `tag_csr1` to decorrelate the two.
+    pub tag_csr2: FoldedHistory,
 }
-impl TAGEComponent { 
+impl TAGEComponent {
     pub fn new(
         entry: TAGEEntry,
-        size: usize, 
+        size: usize,
         ghr_range: RangeInclusive<usize>,
         tag_bits: usize,
         pc_sel_fn: fn(usize) -> usize,
     ) -> Self
     {
         assert!(size.is_power_of_two());
-        Self { 
+        let orig_length = ghr_range.end() - ghr_range.start();
+        Self {
             data: vec![entry; size],
             size,
-            ghr_range: ghr_range.clone(), 
+            ghr_range: ghr_range.clone(),
             tag_bits,
             pc_sel_fn,
-            csr: FoldedHistoryRegister::new(
-                size.ilog2() as usize, 
-                ghr_range.clone(),
-            ),
+            idx_csr: FoldedHistory::new(orig_length, size.ilog2() as usize),
+            tag_csr1: FoldedHistory::new(orig_length, tag_bits),
+            tag_csr2: FoldedHistory::new(orig_length, tag_bits - 1),
         }
     }
+
+    /// Update all three folded-history CSRs with the newest global-history
+    /// bit and the bit retiring out of this component's history window.
+    pub fn update_history(&mut self, ghr: &GlobalHistoryRegister) {
+        let start = *self.ghr_range.start();
+        let orig_length = self.ghr_range.end() - self.ghr_range.start();
+        let h_new = *ghr.read(0..=0).first().unwrap();
+        let h_out_idx = start + orig_length;
+        let h_out = *ghr.read(h_out_idx..=h_out_idx).first().unwrap();
+
+        self.idx_csr.update(h_new, h_out);
+        self.tag_csr1.update(h_new, h_out);
+        self.tag_csr2.update(h_new, h_out);
+    }
+
+    /// Capture this component's CSR state for later [Self::restore].
+    pub fn checkpoint(&self) -> TAGEComponentSnapshot {
+        TAGEComponentSnapshot {
+            idx_csr: self.idx_csr.checkpoint(),
+            tag_csr1: self.tag_csr1.checkpoint(),
+            tag_csr2: self.tag_csr2.checkpoint(),
+        }
+    }
+
+    /// Restore CSR state previously captured with [Self::checkpoint].
+    pub fn restore(&mut self, snapshot: &TAGEComponentSnapshot) {
+        self.idx_csr.restore(&snapshot.idx_csr);
+        self.tag_csr1.restore(&snapshot.tag_csr1);
+        self.tag_csr2.restore(&snapshot.tag_csr2);
+    }
+}
+
+/// A saved [TAGEComponent] CSR state, as produced by
+/// [TAGEComponent::checkpoint].
+#[derive(Clone, Debug)]
+pub struct TAGEComponentSnapshot {
+    idx_csr: FoldedHistorySnapshot,
+    tag_csr1: FoldedHistorySnapshot,
+    tag_csr2: FoldedHistorySnapshot,
 }
 impl PredictorTable for TAGEComponent {
-    type Input = usize;
+    type Input<'a> = usize;
+    type Index = usize;
     type Entry = TAGEEntry;
 
     fn size(&self) -> usize { self.size }
-    fn get_index(&self, pc: usize) -> usize { 
-        let ghist_bits = self.csr.output_usize(); 
+    fn get_index(&self, pc: usize) -> usize {
+        let ghist_bits = self.idx_csr.output();
         let pc_bits = (self.pc_sel_fn)(pc);
         let index = ghist_bits ^ pc_bits;
         index & self.index_mask()
     }
-    fn get_entry(&self, pc: usize) -> &TAGEEntry { 
-        let index = self.get_index(pc);
-        &self.data[index]
+    fn get_entry(&self, idx: usize) -> &TAGEEntry {
+        &self.data[idx]
     }
-    fn get_entry_mut(&mut self, pc: usize) -> &mut TAGEEntry { 
-        let index = self.get_index(pc);
-        &mut self.data[index]
+    fn get_entry_mut(&mut self, idx: usize) -> &mut TAGEEntry {
+        &mut self.data[idx]
     }
 
 }
 
-impl TaggedPredictorTable for TAGEComponent {
-    fn get_tag(&self, pc: usize) -> usize { 
-        let pc_bits = (self.pc_sel_fn)(pc); 
-        let ghist0_bits = self.csr.output_usize();
-        let ghist1_bits = self.csr.output_usize() << 1;
-        pc_bits ^ ghist0_bits ^ ghist1_bits
+impl <'a> TaggedPredictorTable<'a> for TAGEComponent {
+    fn get_tag(&self, pc: usize) -> usize {
+        let pc_bits = (self.pc_sel_fn)(pc);
+        let tag_bits1 = self.tag_csr1.output();
+        let tag_bits2 = self.tag_csr2.output() << 1;
+        pc_bits ^ tag_bits1 ^ tag_bits2
     }
 }
 
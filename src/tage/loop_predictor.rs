@@ -0,0 +1,101 @@
+
+use crate::direction::*;
+
+/// The maximum value of [LoopEntry::confidence]. A loop entry is only
+/// trusted to override the TAGE outcome once it has seen this many
+/// consecutive correctly-predicted iteration counts in a row.
+const LOOP_CONFIDENCE_MAX: u8 = 3;
+
+/// An entry in a [LoopPredictor].
+///
+/// A loop entry assumes the associated branch is the backward branch of a
+/// regularly-iterating loop: taken on every iteration except the last,
+/// where it is not-taken (the loop exits).
+#[derive(Clone, Copy, Debug)]
+pub struct LoopEntry {
+    /// A partial tag used to detect aliasing within the table.
+    pub tag: Option<usize>,
+    /// The number of taken iterations observed the last time this loop ran.
+    pub past_iter_count: usize,
+    /// The number of taken iterations observed so far in the current run.
+    pub current_iter: usize,
+    /// Confidence that [LoopEntry::past_iter_count] correctly predicts the
+    /// length of the next run of the loop. Saturates at
+    /// [LOOP_CONFIDENCE_MAX].
+    pub confidence: u8,
+    /// Number of times this entry has completed a run of the loop. A tag
+    /// mismatch replaces the entry whatever its age.
+    pub age: u8,
+}
+impl LoopEntry {
+    fn new() -> Self {
+        Self { tag: None, past_iter_count: 0, current_iter: 0, confidence: 0, age: 0 }
+    }
+}
+
+/// A loop predictor, as in the L-TAGE design: a small table of
+/// [LoopEntry] indexed by a folded program counter, used to override
+/// [crate::ltage::TAGEPredictor] on regularly-iterating loop branches that
+/// TAGE's geometric history lengths tend to mispredict on the final,
+/// loop-exiting iteration.
+pub struct LoopPredictor {
+    data: Vec<LoopEntry>,
+    size: usize,
+    index_fn: fn(usize) -> usize,
+}
+impl LoopPredictor {
+    /// Create a new loop predictor with `size` entries (a power of two),
+    /// indexed by folding a program counter value through `index_fn`.
+    pub fn new(size: usize, index_fn: fn(usize) -> usize) -> Self {
+        assert!(size.is_power_of_two());
+        Self { data: vec![LoopEntry::new(); size], size, index_fn }
+    }
+
+    fn index_mask(&self) -> usize { self.size - 1 }
+    fn index_of(&self, pc: usize) -> usize { (self.index_fn)(pc) & self.index_mask() }
+    fn tag_of(&self, pc: usize) -> usize { (self.index_fn)(pc) >> self.size.ilog2() }
+
+    /// Return a direction, overriding the TAGE prediction, if and only if
+    /// the entry for `pc` has a matching tag and maximal confidence.
+    /// Otherwise, return `None` and defer to TAGE.
+    pub fn predict(&self, pc: usize) -> Option<Outcome> {
+        let entry = &self.data[self.index_of(pc)];
+        let tag = self.tag_of(pc);
+        if entry.tag != Some(tag) || entry.confidence < LOOP_CONFIDENCE_MAX {
+            return None;
+        }
+        if entry.current_iter < entry.past_iter_count {
+            Some(Outcome::T)
+        } else {
+            Some(Outcome::N)
+        }
+    }
+
+    /// Update the entry for `pc` with the resolved branch `outcome`.
+    /// Allocates (resetting) the entry on a tag mismatch.
+    pub fn update(&mut self, pc: usize, outcome: Outcome) {
+        let tag = self.tag_of(pc);
+        let entry = &mut self.data[self.index_of(pc)];
+        if entry.tag != Some(tag) {
+            *entry = LoopEntry { tag: Some(tag), ..LoopEntry::new() };
+        }
+
+        match outcome {
+            // Still iterating through the loop body.
+            Outcome::T => entry.current_iter += 1,
+
+            // The loop has exited - check whether the observed iteration
+            // count matched what we expected.
+            Outcome::N => {
+                if entry.current_iter == entry.past_iter_count {
+                    entry.confidence = (entry.confidence + 1).min(LOOP_CONFIDENCE_MAX);
+                } else {
+                    entry.confidence = 0;
+                    entry.past_iter_count = entry.current_iter;
+                }
+                entry.current_iter = 0;
+                entry.age = entry.age.saturating_add(1);
+            },
+        }
+    }
+}
@@ -0,0 +1,200 @@
+
+use crate::direction::*;
+use crate::history::*;
+use super::component::FoldedHistory;
+
+/// Lower bound on [StatisticalCorrector]'s adaptive threshold, so it can
+/// never collapse to the point where every disagreement overrides TAGE.
+const SC_THETA_MIN: i32 = 2;
+
+/// One bank of signed, saturating counters in a [StatisticalCorrector],
+/// indexed by folding some piece of state (history, path, or PC) down to
+/// `log_size` bits.
+pub struct SCBank {
+    data: Vec<i8>,
+    log_size: usize,
+}
+impl SCBank {
+    pub fn new(log_size: usize) -> Self {
+        Self { data: vec![0i8; 1 << log_size], log_size }
+    }
+
+    fn index(&self, hash: usize) -> usize { hash & ((1 << self.log_size) - 1) }
+
+    /// Read the counter selected by `hash`.
+    pub fn get(&self, hash: usize) -> i8 {
+        self.data[self.index(hash)]
+    }
+
+    /// Train the counter selected by `hash` toward `outcome`.
+    pub fn train(&mut self, hash: usize, outcome: Outcome) {
+        let idx = self.index(hash);
+        match outcome {
+            Outcome::T => self.data[idx] = self.data[idx].saturating_add(1),
+            Outcome::N => self.data[idx] = self.data[idx].saturating_sub(1),
+        }
+    }
+}
+
+/// The "statistical corrector" (SC) stage from TAGE-SC-L: a GEHL-style
+/// ensemble of signed-counter banks that post-processes a TAGE prediction,
+/// catching the cases systematic to TAGE's geometric-history design that
+/// no single tagged component can represent.
+///
+/// See "A 64kbits ISL-TAGE branch predictor" (Seznec, 2011) and
+/// "TAGE-SC-L branch predictors" (Seznec, 2014).
+pub struct StatisticalCorrector {
+    /// Folded-history CSRs, one per GEHL bank, each at a different
+    /// geometric history length.
+    gehl_csrs: Vec<FoldedHistory>,
+    /// GEHL banks, indexed by `gehl_csrs`' folded output.
+    gehl_banks: Vec<SCBank>,
+    /// Bank indexed by path history.
+    path_bank: SCBank,
+    /// Bank biased purely by the program counter.
+    pc_bank: SCBank,
+
+    /// Adaptive decision threshold: the signed sum must reach this
+    /// magnitude (in the direction opposite the TAGE provider) to
+    /// override it.
+    theta: i32,
+
+    /// Number of predictions where SC overrode the TAGE provider.
+    corrections: u64,
+    /// Number of those overrides that turned out to be correct.
+    correct_corrections: u64,
+}
+impl StatisticalCorrector {
+    /// Build a statistical corrector with GEHL banks folding global
+    /// history at each length in `gehl_lengths`, all banks sized to
+    /// `bank_log_size` bits (except the path/PC banks, sized to
+    /// `path_log_size`/`pc_log_size`).
+    pub fn new(
+        gehl_lengths: &[usize],
+        bank_log_size: usize,
+        path_log_size: usize,
+        pc_log_size: usize,
+    ) -> Self
+    {
+        let gehl_csrs = gehl_lengths.iter()
+            .map(|len| FoldedHistory::new(*len, bank_log_size))
+            .collect();
+        let gehl_banks = gehl_lengths.iter()
+            .map(|_| SCBank::new(bank_log_size))
+            .collect();
+
+        Self {
+            gehl_csrs,
+            gehl_banks,
+            path_bank: SCBank::new(path_log_size),
+            pc_bank: SCBank::new(pc_log_size),
+            theta: 8,
+            corrections: 0,
+            correct_corrections: 0,
+        }
+    }
+
+    fn path_hash(&self, phr: &GlobalHistoryRegister) -> usize {
+        phr.fold(0..=31, self.path_bank.log_size)
+    }
+
+    /// Given the TAGE provider's own prediction and its confidence (the
+    /// magnitude of the counter that produced it), decide whether to keep
+    /// it or flip it. Returns the final outcome and whether SC overrode
+    /// the provider.
+    pub fn predict(
+        &self,
+        pc: usize,
+        phr: &GlobalHistoryRegister,
+        provider_outcome: Outcome,
+        provider_confidence: u8,
+    ) -> (Outcome, bool)
+    {
+        let mut sum: i32 = 0;
+        for (csr, bank) in self.gehl_csrs.iter().zip(self.gehl_banks.iter()) {
+            sum += bank.get(csr.output()) as i32;
+        }
+        sum += self.path_bank.get(self.path_hash(phr)) as i32;
+        sum += self.pc_bank.get(pc) as i32;
+
+        // A scaled vote for the TAGE provider's own direction - the more
+        // confident the provider is, the more the ensemble has to
+        // disagree (and by how much) before SC is willing to overrule it.
+        sum += match provider_outcome {
+            Outcome::T => provider_confidence as i32,
+            Outcome::N => -(provider_confidence as i32),
+        };
+
+        let sc_outcome = if sum >= 0 { Outcome::T } else { Outcome::N };
+        let sc_override = sc_outcome != provider_outcome && sum.abs() >= self.theta;
+        let outcome = if sc_override { sc_outcome } else { provider_outcome };
+        (outcome, sc_override)
+    }
+
+    /// Train every selected bank counter toward the resolved `outcome`,
+    /// and adapt the decision threshold based on whether the prediction
+    /// made with `sc_override` was correct.
+    pub fn update(
+        &mut self,
+        pc: usize,
+        phr: &GlobalHistoryRegister,
+        predicted: Outcome,
+        sc_override: bool,
+        outcome: Outcome,
+    )
+    {
+        if sc_override {
+            self.corrections += 1;
+            if predicted == outcome {
+                self.correct_corrections += 1;
+            }
+        }
+
+        if predicted != outcome {
+            self.theta = self.theta.saturating_add(1);
+        } else {
+            let sum = self.current_margin(pc, phr);
+            if sum.abs() <= self.theta {
+                self.theta = (self.theta - 1).max(SC_THETA_MIN);
+            }
+        }
+
+        for (csr, bank) in self.gehl_csrs.iter_mut().zip(self.gehl_banks.iter_mut()) {
+            bank.train(csr.output(), outcome);
+        }
+        let path_hash = self.path_hash(phr);
+        self.path_bank.train(path_hash, outcome);
+        self.pc_bank.train(pc, outcome);
+    }
+
+    /// Re-derive the signed sum behind a prediction (minus the provider
+    /// confidence term, which [StatisticalCorrector::update] doesn't
+    /// have on hand), for the low-margin threshold check in
+    /// [StatisticalCorrector::update].
+    fn current_margin(&self, pc: usize, phr: &GlobalHistoryRegister) -> i32 {
+        let mut sum: i32 = 0;
+        for (csr, bank) in self.gehl_csrs.iter().zip(self.gehl_banks.iter()) {
+            sum += bank.get(csr.output()) as i32;
+        }
+        sum += self.path_bank.get(self.path_hash(phr)) as i32;
+        sum += self.pc_bank.get(pc) as i32;
+        sum
+    }
+
+    /// Update all of SC's folded-history CSRs with the newest
+    /// global-history bit.
+    pub fn update_history(&mut self, ghr: &GlobalHistoryRegister) {
+        let h_new = *ghr.read(0..=0).first().unwrap();
+        for csr in self.gehl_csrs.iter_mut() {
+            let out_idx = csr.history_length();
+            let h_out = *ghr.read(out_idx..=out_idx).first().unwrap();
+            csr.update(h_new, h_out);
+        }
+    }
+
+    /// Number of predictions where SC overrode the TAGE provider.
+    pub fn corrections(&self) -> u64 { self.corrections }
+
+    /// Number of SC overrides that were correct.
+    pub fn correct_corrections(&self) -> u64 { self.correct_corrections }
+}